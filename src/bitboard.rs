@@ -0,0 +1,345 @@
+use crate::board::{self, Board};
+use crate::hex::{Hex, DIAGONAL_DIRECTIONS, KNIGHT_OFFSETS, ORTHOGONAL_DIRECTIONS};
+use crate::moves::{self, Move, MoveDirection};
+use crate::pieces::{self, ChessPiece, PieceColor, PieceType};
+use std::sync::OnceLock;
+
+/// Number of cells on the board. Cell `i` is bit `i` of a mask.
+pub const CELL_COUNT: usize = 91;
+
+/// Piece types in the order of `Bitboard.pieces`.
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King
+];
+
+/// Precomputed geometry of the board, indexed by cell.
+struct Tables {
+    coordinates: [(usize, usize); CELL_COUNT],
+    indices: [[Option<usize>; 11]; 11],
+    knight: [u128; CELL_COUNT],
+    king: [u128; CELL_COUNT],
+    /// Cells from each cell to the edge, for the 6 orthogonal directions followed by the 6 diagonal ones.
+    rays: [[u128; CELL_COUNT]; 12],
+    /// Cells a Pawn of each colour attacks.
+    pawn_captures: [[u128; CELL_COUNT]; 2],
+    /// The cell in front of a Pawn of each colour.
+    pawn_steps: [[Option<usize>; CELL_COUNT]; 2],
+    /// Cells from which a Pawn of each colour may step two cells forward.
+    pawn_starts: [u128; 2]
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn get_tables() -> &'static Tables {
+    TABLES.get_or_init(|| {
+        let mut coordinates = [(0, 0); CELL_COUNT];
+        let mut indices = [[None; 11]; 11];
+        let cells = (0..11).flat_map(|x| (0..11).map(move |y| (x, y))).filter(moves::is_coordinate_in_bounds);
+        for (index, cell) in cells.enumerate() {
+            coordinates[index] = cell;
+            indices[cell.0][cell.1] = Some(index);
+        }
+        let get_mask = |cells: &mut dyn Iterator<Item = Hex>| -> u128 {
+            cells.filter_map(Hex::to_coordinates)
+                .fold(0, |mask, (x, y)| mask | indices[x][y].map_or(0, |index| 1u128 << index))
+        };
+
+        let mut tables = Tables {
+            coordinates,
+            indices,
+            knight: [0; CELL_COUNT],
+            king: [0; CELL_COUNT],
+            rays: [[0; CELL_COUNT]; 12],
+            pawn_captures: [[0; CELL_COUNT]; 2],
+            pawn_steps: [[None; CELL_COUNT]; 2],
+            pawn_starts: [0; 2]
+        };
+        let directions: Vec<Hex> = ORTHOGONAL_DIRECTIONS.into_iter().chain(DIAGONAL_DIRECTIONS).collect();
+        let pawn_directions = [
+            (MoveDirection::Up, [MoveDirection::LeftUp, MoveDirection::RightUp]),
+            (MoveDirection::Down, [MoveDirection::RightDown, MoveDirection::LeftDown])
+        ];
+        for (index, cell) in coordinates.iter().enumerate() {
+            let hex = Hex::from_coordinates(cell);
+            tables.knight[index] = get_mask(&mut KNIGHT_OFFSETS.into_iter().map(|offset| hex + offset));
+            tables.king[index] = get_mask(&mut hex.get_orthogonal_neighbours().chain(hex.get_diagonal_neighbours()));
+            for (direction_index, direction) in directions.iter().enumerate() {
+                tables.rays[direction_index][index] = get_mask(&mut hex.get_ray(*direction));
+            }
+            for (color_index, (forward, captures)) in pawn_directions.iter().enumerate() {
+                let capture_cells = captures.map(|direction| hex + moves::get_direction_offset(direction));
+                tables.pawn_captures[color_index][index] = get_mask(&mut capture_cells.into_iter());
+                tables.pawn_steps[color_index][index] = moves::get_move_coordinate(*forward, cell)
+                    .and_then(|(x, y)| indices[x][y]);
+            }
+        }
+        for (color_index, cells) in moves::PAWN_START_CELLS.iter().enumerate() {
+            tables.pawn_starts[color_index] = get_mask(&mut cells.iter().map(Hex::from_coordinates));
+        }
+        tables
+    })
+}
+
+/// Returns the bit index of a cell, or `None` if it isn't on the board.
+pub fn get_cell_index(coordinates: &(usize, usize)) -> Option<usize> {
+    get_tables().indices.get(coordinates.0)?.get(coordinates.1).copied().flatten()
+}
+
+/// Returns the `Board.0` index of the cell with bit index `index`. Cells are numbered row by row.
+pub fn get_cell_coordinates(index: usize) -> (usize, usize) {
+    get_tables().coordinates[index]
+}
+
+fn get_color_index(color: PieceColor) -> Option<usize> {
+    match color {
+        PieceColor::White => Some(0),
+        PieceColor::Black => Some(1),
+        PieceColor::None => None
+    }
+}
+
+fn get_type_index(piece_type: PieceType) -> Option<usize> {
+    PIECE_TYPES.iter().position(|candidate| *candidate == piece_type)
+}
+
+/// Iterates the indices of the set bits, lowest first.
+fn get_set_bits(mut mask: u128) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let index = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(index)
+    })
+}
+
+/// Returns the cells a slider reaches along the rays, up to and including the first occupied cell of each.
+fn get_sliding_attacks(index: usize, occupied: u128, directions: std::ops::Range<usize>) -> u128 {
+    let tables = get_tables();
+    let mut attacks = 0;
+    for direction in directions {
+        let ray = tables.rays[direction][index];
+        let blockers = ray & occupied;
+        if blockers == 0 {
+            attacks |= ray;
+            continue;
+        }
+        // Cells are numbered row by row, so the nearest blocker has the lowest index on rays running down the
+        // board (or right to left along a row) and the highest on the others.
+        let ascending = ray.trailing_zeros() as usize > index;
+        let blocker = if ascending { blockers.trailing_zeros() as usize } else { 127 - blockers.leading_zeros() as usize };
+        attacks |= ray & !tables.rays[direction][blocker];
+    }
+    attacks
+}
+
+/// The board as one mask per colour and one per piece type. A piece is on a cell when the cell's bit is set in its
+/// colour's mask and its type's mask.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bitboard {
+    pub colors: [u128; 2],
+    pub pieces: [u128; 6]
+}
+
+impl Bitboard {
+    pub fn from_board(board: &Board) -> Bitboard {
+        let mut bitboard = Bitboard::default();
+        for index in 0..CELL_COUNT {
+            let (x, y) = get_cell_coordinates(index);
+            if let Some(piece) = board.0[x][y] {
+                bitboard.set_piece(index, piece);
+            }
+        }
+        bitboard
+    }
+
+    /// Converts back to the array board, with empty cells holding a `PieceType::None` piece.
+    pub fn to_board(self) -> Board {
+        let mut board = board::new();
+        for index in get_set_bits(self.get_occupied()) {
+            let (x, y) = get_cell_coordinates(index);
+            board.0[x][y] = self.get_piece(index);
+        }
+        board
+    }
+
+    /// Puts the piece on the empty cell. `PieceType::None` pieces are ignored.
+    pub fn set_piece(&mut self, index: usize, piece: ChessPiece) {
+        if let (Some(color), Some(piece_type)) = (get_color_index(piece.color), get_type_index(piece.piece_type)) {
+            self.colors[color] |= 1 << index;
+            self.pieces[piece_type] |= 1 << index;
+        }
+    }
+
+    pub fn get_occupied(&self) -> u128 {
+        self.colors[0] | self.colors[1]
+    }
+
+    pub fn get_piece_mask(&self, color: PieceColor, piece_type: PieceType) -> u128 {
+        match (get_color_index(color), get_type_index(piece_type)) {
+            (Some(color), Some(piece_type)) => self.colors[color] & self.pieces[piece_type],
+            _ => 0
+        }
+    }
+
+    pub fn get_piece(&self, index: usize) -> Option<ChessPiece> {
+        let bit = 1u128 << index;
+        let color = if self.colors[0] & bit != 0 {
+            PieceColor::White
+        } else if self.colors[1] & bit != 0 {
+            PieceColor::Black
+        } else {
+            return None;
+        };
+        let piece_type = PIECE_TYPES[self.pieces.iter().position(|mask| mask & bit != 0)?];
+        Some(ChessPiece { piece_type, color })
+    }
+
+    /// Returns the cells the piece attacks from `index`. For Pawns these are the capture cells only.
+    pub fn get_attacks(&self, index: usize, piece: ChessPiece) -> u128 {
+        let tables = get_tables();
+        let occupied = self.get_occupied();
+        match piece.piece_type {
+            PieceType::Pawn => get_color_index(piece.color).map_or(0, |color| tables.pawn_captures[color][index]),
+            PieceType::Knight => tables.knight[index],
+            PieceType::Bishop => get_sliding_attacks(index, occupied, 6..12),
+            PieceType::Rook => get_sliding_attacks(index, occupied, 0..6),
+            PieceType::Queen => get_sliding_attacks(index, occupied, 0..12),
+            PieceType::King => tables.king[index],
+            PieceType::None => 0
+        }
+    }
+
+    /// Checks if any piece of `by_color` attacks the cell.
+    pub fn is_attacked(&self, index: usize, by_color: PieceColor) -> bool {
+        let (Some(color), Some(opponent)) = (get_color_index(by_color), get_color_index(pieces::get_opponent_color(by_color))) else {
+            return false;
+        };
+        let tables = get_tables();
+        let occupied = self.get_occupied();
+        let attackers = self.colors[color];
+        let [pawns, knights, bishops, rooks, queens, kings] = self.pieces.map(|mask| mask & attackers);
+
+        // A Pawn of `by_color` attacks the cell exactly when a Pawn of the other colour on the cell would attack it.
+        tables.pawn_captures[opponent][index] & pawns != 0
+            || tables.knight[index] & knights != 0
+            || tables.king[index] & kings != 0
+            || get_sliding_attacks(index, occupied, 6..12) & (bishops | queens) != 0
+            || get_sliding_attacks(index, occupied, 0..6) & (rooks | queens) != 0
+    }
+
+    /// Checks if the King of `color` is attacked. A side without a King is never in Check.
+    pub fn is_check(&self, color: PieceColor) -> bool {
+        match get_set_bits(self.get_piece_mask(color, PieceType::King)).next() {
+            Some(king) => self.is_attacked(king, pieces::get_opponent_color(color)),
+            None => false
+        }
+    }
+
+    /// Moves the piece on `from` to `to`, removing whatever is on `captured_at`. Promotions aren't applied.
+    fn shift_piece(&mut self, from: usize, to: usize, captured_at: usize) {
+        let captured = !(1u128 << captured_at);
+        for mask in self.colors.iter_mut().chain(self.pieces.iter_mut()) {
+            *mask &= captured;
+            if *mask & (1 << from) != 0 {
+                *mask ^= (1 << from) | (1 << to);
+            }
+        }
+    }
+
+    /// Returns the moves of `color` that don't leave its own King in Check.
+    ///
+    /// Like `moves::get_all_legal_moves`, a promotion is listed once with `promotion` set to `None`.
+    pub fn get_legal_moves(&self, color: PieceColor, en_passant: Option<(usize, usize)>) -> Vec<Move> {
+        let mut legal_moves: Vec<Move> = Vec::new();
+        let Some(color_index) = get_color_index(color) else { return legal_moves };
+        let tables = get_tables();
+        let own = self.colors[color_index];
+        let opponents = self.colors[1 - color_index];
+        let occupied = own | opponents;
+        let en_passant = en_passant.and_then(|target| get_cell_index(&target));
+
+        for from in get_set_bits(own) {
+            let Some(piece) = self.get_piece(from) else { continue };
+            let mut targets = self.get_attacks(from, piece) & !own;
+            // Each target paired with the cell of the piece it captures
+            let mut captures_at: Vec<(usize, usize)> = Vec::new();
+
+            if piece.piece_type == PieceType::Pawn {
+                targets &= opponents;
+                if let Some(target) = en_passant.filter(|target| tables.pawn_captures[color_index][from] & (1 << target) != 0) {
+                    let victim = tables.pawn_steps[1 - color_index][target];
+                    let pawns = self.get_piece_mask(pieces::get_opponent_color(color), PieceType::Pawn);
+                    if let Some(victim) = victim.filter(|victim| occupied & (1 << target) == 0 && pawns & (1 << victim) != 0) {
+                        captures_at.push((target, victim));
+                    }
+                }
+                if let Some(step) = tables.pawn_steps[color_index][from].filter(|step| occupied & (1 << step) == 0) {
+                    targets |= 1 << step;
+                    let double_step = tables.pawn_steps[color_index][step];
+                    if let Some(double_step) = double_step.filter(|_| tables.pawn_starts[color_index] & (1 << from) != 0) {
+                        if occupied & (1 << double_step) == 0 {
+                            targets |= 1 << double_step;
+                        }
+                    }
+                }
+            }
+            captures_at.extend(get_set_bits(targets).map(|to| (to, to)));
+
+            for (to, captured_at) in captures_at {
+                let mut after = *self;
+                after.shift_piece(from, to, captured_at);
+                if !after.is_check(color) {
+                    legal_moves.push(Move {
+                        piece,
+                        from: get_cell_coordinates(from),
+                        to: get_cell_coordinates(to),
+                        promotion: None
+                    });
+                }
+            }
+        }
+        legal_moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position;
+    use crate::test_support::RandomGame;
+    use std::collections::HashSet;
+
+    fn get_array_moves(board: &Board, color: PieceColor, en_passant: Option<(usize, usize)>) -> HashSet<((usize, usize), (usize, usize))> {
+        moves::get_all_legal_moves(board, color, en_passant)
+            .into_iter()
+            .flat_map(|(from, to_list)| to_list.into_iter().map(move |to| (from, to)))
+            .collect()
+    }
+
+    #[test]
+    fn converts_to_and_from_the_array_board() {
+        let board = board::get_default_board();
+        let bitboard = Bitboard::from_board(&board);
+        assert_eq!(bitboard.get_occupied().count_ones(), 36);
+        assert_eq!(bitboard.get_piece_mask(PieceColor::White, PieceType::Bishop).count_ones(), 3);
+        assert_eq!(bitboard.to_board(), board);
+        for index in 0..CELL_COUNT {
+            assert_eq!(get_cell_index(&get_cell_coordinates(index)), Some(index));
+        }
+    }
+
+    #[test]
+    fn matches_array_move_generation() {
+        for seed in 0..12u64 {
+            for (current, _) in RandomGame::new(position::get_default_position(), seed).take(100) {
+                let bitboard = Bitboard::from_board(&current.board);
+                let legal_moves = bitboard.get_legal_moves(current.next_turn, current.en_passant);
+                let bitboard_moves: HashSet<_> = legal_moves.iter().map(|move_info| (move_info.from, move_info.to)).collect();
+                assert_eq!(bitboard_moves, get_array_moves(&current.board, current.next_turn, current.en_passant));
+                assert_eq!(bitboard.is_check(current.next_turn), crate::validation::is_check(&current.board, current.next_turn));
+            }
+        }
+    }
+}
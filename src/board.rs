@@ -1,7 +1,10 @@
+use crate::moves;
 use crate::pieces::*;
+use std::fmt;
 use std::fs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Board(pub [[Option<ChessPiece>; 11]; 11]);
 
 pub fn new() -> Board {
@@ -11,68 +14,163 @@ pub fn new() -> Board {
     }); 11]; 11])
 }
 
+/// Reasons a board file can't be loaded. Lines and entries are counted from 1.
+#[derive(Debug)]
+pub enum BoardFileError {
+    /// The file couldn't be read.
+    Io(std::io::Error),
+    /// The entry isn't of the form "cell:piece" with a numeric cell from 0 to 255.
+    MalformedEntry { line: usize, entry: usize, text: String },
+    /// The piece code isn't a colour ("W" or "B") followed by a piece letter.
+    UnknownPiece { line: usize, entry: usize, code: String },
+    /// The encoded cell isn't one of the 91 cells of the board.
+    OffBoard { line: usize, entry: usize, coordinates: (usize, usize) },
+    /// The cell already has a piece from an earlier entry.
+    DuplicateCell { line: usize, entry: usize, coordinates: (usize, usize) },
+    /// The side has no King.
+    MissingKing(PieceColor),
+    /// The side already has a King from an earlier entry.
+    ExtraKing { line: usize, entry: usize, color: PieceColor }
+}
+
+impl fmt::Display for BoardFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardFileError::Io(error) => write!(f, "Failed to read the board file: {}", error),
+            BoardFileError::MalformedEntry { line, entry, text } => {
+                write!(f, "Line {}, entry {}: malformed entry \"{}\"", line, entry, text)
+            }
+            BoardFileError::UnknownPiece { line, entry, code } => {
+                write!(f, "Line {}, entry {}: unknown piece code \"{}\"", line, entry, code)
+            }
+            BoardFileError::OffBoard { line, entry, coordinates } => {
+                write!(f, "Line {}, entry {}: cell {:?} is off the board", line, entry, coordinates)
+            }
+            BoardFileError::DuplicateCell { line, entry, coordinates } => {
+                write!(f, "Line {}, entry {}: cell {:?} is already occupied", line, entry, coordinates)
+            }
+            BoardFileError::MissingKing(color) => write!(f, "{:?} has no King", color),
+            BoardFileError::ExtraKing { line, entry, color } => {
+                write!(f, "Line {}, entry {}: {:?} already has a King", line, entry, color)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BoardFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BoardFileError::Io(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<std::io::Error> for BoardFileError {
+    fn from(error: std::io::Error) -> Self {
+        BoardFileError::Io(error)
+    }
+}
+
+/// Packs a coordinate into one byte, `x` in the high nibble and `y` in the low nibble.
+pub fn encode_coordinates_bitwise(coordinates: &(usize, usize)) -> usize {
+    (coordinates.0 << 4) | coordinates.1
+}
+
 pub fn decode_coordinates_bitwise(encoded_value: usize) -> (usize, usize) {
     let x = (encoded_value & 0xF0) >> 4;
     let y = encoded_value & 0x0F;
     (x, y)
 }
 
+/// Decodes a piece code like "WK" or "BP". Returns None for anything else.
 pub fn decode_piece(piece_code: &str) -> Option<ChessPiece> {
-    let color_code = piece_code.chars().nth(0).unwrap();
-    let type_code = piece_code.chars().nth(1).unwrap();
+    let mut chars = piece_code.chars();
+    let color_code = chars.next()?;
+    let type_code = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
 
     Some(ChessPiece {
-        piece_type: {
-            match type_code {
-                'B' => PieceType::Bishop,
-                'K' => PieceType::King,
-                'N' => PieceType::Knight,
-                'Q' => PieceType::Queen,
-                'R' => PieceType::Rook,
-                _ => PieceType::Pawn,
-            }
+        piece_type: match type_code {
+            'B' => PieceType::Bishop,
+            'K' => PieceType::King,
+            'N' => PieceType::Knight,
+            'Q' => PieceType::Queen,
+            'R' => PieceType::Rook,
+            'P' => PieceType::Pawn,
+            _ => return None,
         },
-        color: {
-            match color_code {
-                'B' => PieceColor::Black,
-                'W' => PieceColor::White,
-                _ => PieceColor::White,
-            }
+        color: match color_code {
+            'B' => PieceColor::Black,
+            'W' => PieceColor::White,
+            _ => return None,
         },
     })
 }
 
-pub fn get_board_from_file(file_path: &str) -> Board {
-    let mut board: Board = Board([[Some(ChessPiece {
-        piece_type: PieceType::None,
-        color: PieceColor::None,
-    }); 11]; 11]);
-
-    let input_string = fs::read_to_string(file_path).expect("Failed to read the file");
+/// Reads a board from ";" separated "cell:piece" entries, where the cell is `x << 4 | y`.
+/// 
+/// Entries may be spread over several lines. Each side must have exactly one King.
+pub fn parse_board(contents: &str) -> Result<Board, BoardFileError> {
+    let mut board = new();
+    let mut occupied = [[false; 11]; 11];
+    let mut kings = [false; 2];
+    let mut entry = 0;
 
-    let cleaned_string = input_string.replace("\n", "").trim().to_string();
+    for (line_index, line_text) in contents.lines().enumerate() {
+        let line = line_index + 1;
+        for value in line_text.split(';').map(str::trim).filter(|value| !value.is_empty()) {
+            entry += 1;
+            let malformed = || BoardFileError::MalformedEntry { line, entry, text: value.to_string() };
 
-    let cleaned_string_with_semicolon = if !cleaned_string.ends_with(';') {
-        format!("{};", cleaned_string)
-    } else {
-        cleaned_string
-    };
+            let (cell_code, piece_code) = value.split_once(':').ok_or_else(malformed)?;
+            let encoded_value: usize = cell_code.trim().parse().map_err(|_| malformed())?;
+            if encoded_value > 0xFF {
+                return Err(malformed());
+            }
+            let piece_code = piece_code.trim();
+            let piece = decode_piece(piece_code).ok_or_else(|| BoardFileError::UnknownPiece {
+                line,
+                entry,
+                code: piece_code.to_string()
+            })?;
 
-    let values: Vec<&str> = cleaned_string_with_semicolon.split(";").collect();
+            let coordinates = decode_coordinates_bitwise(encoded_value);
+            if !moves::is_coordinate_in_bounds(&coordinates) {
+                return Err(BoardFileError::OffBoard { line, entry, coordinates });
+            }
+            let (x, y) = coordinates;
+            if occupied[x][y] {
+                return Err(BoardFileError::DuplicateCell { line, entry, coordinates });
+            }
+            if piece.piece_type == PieceType::King {
+                let side = if piece.color == PieceColor::White { 0 } else { 1 };
+                if kings[side] {
+                    return Err(BoardFileError::ExtraKing { line, entry, color: piece.color });
+                }
+                kings[side] = true;
+            }
 
-    for value in values {
-        let parts: Vec<&str> = value.split(":").collect();
-        if parts.len() == 2 && !parts[0].is_empty() && !parts[1].is_empty() {
-            let encoded_value: usize = parts[0]
-                .parse()
-                .expect("Failed to parse the encoded value.");
-            let (i, j) = decode_coordinates_bitwise(encoded_value);
-            let piece = decode_piece(parts[1]);
-            board.0[i][j] = piece;
+            occupied[x][y] = true;
+            board.0[x][y] = Some(piece);
         }
     }
 
-    board
+    if !kings[0] {
+        return Err(BoardFileError::MissingKing(PieceColor::White));
+    }
+    if !kings[1] {
+        return Err(BoardFileError::MissingKing(PieceColor::Black));
+    }
+
+    Ok(board)
+}
+
+pub fn get_board_from_file(file_path: &str) -> Result<Board, BoardFileError> {
+    let contents = fs::read_to_string(file_path)?;
+    parse_board(&contents)
 }
 
 pub fn get_default_board() -> Board {
@@ -289,3 +387,57 @@ pub fn print_board(board: &Board) {
         println!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // White King on (10, 4) and Black King on (1, 4).
+    const KINGS: &str = "164:WK;20:BK;";
+
+    #[test]
+    fn parses_entries_over_several_lines() {
+        let board = parse_board(format!("{}\n101:WP;\n\n37:BR", KINGS).as_str()).unwrap();
+        assert_eq!(board.0[10][4], decode_piece("WK"));
+        assert_eq!(board.0[1][4], decode_piece("BK"));
+        assert_eq!(board.0[6][5], decode_piece("WP"));
+        assert_eq!(board.0[2][5], decode_piece("BR"));
+    }
+
+    #[test]
+    fn reports_bad_entries_with_their_position() {
+        let error = parse_board(format!("{}\n101:WP;101", KINGS).as_str()).unwrap_err();
+        assert!(matches!(error, BoardFileError::MalformedEntry { line: 2, entry: 4, .. }));
+
+        let error = parse_board(format!("{}\nx:WP", KINGS).as_str()).unwrap_err();
+        assert!(matches!(error, BoardFileError::MalformedEntry { line: 2, entry: 3, .. }));
+
+        // 420 would wrap to 164, the White King's cell, if only its low byte were decoded.
+        let error = parse_board(format!("{}420:WP", KINGS).as_str()).unwrap_err();
+        assert_eq!(error.to_string(), "Line 1, entry 3: malformed entry \"420:WP\"");
+
+        let error = parse_board(format!("{}101:WX", KINGS).as_str()).unwrap_err();
+        assert!(matches!(error, BoardFileError::UnknownPiece { line: 1, entry: 3, .. }));
+
+        let error = parse_board(format!("{}0:WP", KINGS).as_str()).unwrap_err();
+        assert!(matches!(error, BoardFileError::OffBoard { coordinates: (0, 0), .. }));
+
+        let error = parse_board(format!("{}164:WP", KINGS).as_str()).unwrap_err();
+        assert!(matches!(error, BoardFileError::DuplicateCell { coordinates: (10, 4), .. }));
+    }
+
+    #[test]
+    fn requires_one_king_per_side() {
+        let error = parse_board("164:WK").unwrap_err();
+        assert!(matches!(error, BoardFileError::MissingKing(PieceColor::Black)));
+
+        let error = parse_board(format!("{}101:BK", KINGS).as_str()).unwrap_err();
+        assert!(matches!(error, BoardFileError::ExtraKing { entry: 3, color: PieceColor::Black, .. }));
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        let error = get_board_from_file("no/such/board.txt").unwrap_err();
+        assert!(matches!(error, BoardFileError::Io(_)));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::moves::is_coordinate_in_bounds;
+use std::fmt;
+
+/// File letters of Glinski notation, from the left edge of the board to the right. There is no "j" file.
+const FILES: [char; 11] = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'k', 'l'];
+
+/// A cell of the hexagonal board.
+/// 
+/// `x` and `y` are the indices into `Board.0`, while `file` and `rank` give the cell's name in Glinski notation,
+/// from "a1" in the bottom left corner to "f11" at the top and "l1" in the bottom right corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cell {
+    pub x: usize,
+    pub y: usize
+}
+
+impl Cell {
+    /// Returns the cell at `Board.0[x][y]`, or `None` if it isn't on the hexagonal board.
+    pub fn new(x: usize, y: usize) -> Option<Cell> {
+        if is_coordinate_in_bounds(&(x, y)) {
+            Some(Cell { x, y })
+        } else {
+            None
+        }
+    }
+
+    pub fn from_coordinates(coordinates: &(usize, usize)) -> Option<Cell> {
+        Cell::new(coordinates.0, coordinates.1)
+    }
+
+    pub fn to_coordinates(self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    /// Index of the bottom cell of file `y`, which is rank 1.
+    fn get_first_rank_x(y: usize) -> usize {
+        10.min(15 - y)
+    }
+
+    /// Parses a cell in Glinski notation, e.g. "f5" or "b10". Returns `None` for anything that isn't a cell on the board.
+    pub fn from_notation(notation: &str) -> Option<Cell> {
+        let mut chars = notation.trim().chars();
+        let file = chars.next()?.to_ascii_lowercase();
+        let rank: usize = chars.as_str().parse().ok()?;
+        let y = 10 - FILES.iter().position(|f| *f == file)?;
+        if rank == 0 || rank > Cell::get_first_rank_x(y) + 1 {
+            return None;
+        }
+        Cell::new(Cell::get_first_rank_x(y) + 1 - rank, y)
+    }
+
+    pub fn get_file(&self) -> char {
+        FILES[10 - self.y]
+    }
+
+    pub fn get_rank(&self) -> usize {
+        Cell::get_first_rank_x(self.y) - self.x + 1
+    }
+
+    pub fn to_notation(self) -> String {
+        format!("{}{}", self.get_file(), self.get_rank())
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_notation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboard::{self, CELL_COUNT};
+    use std::collections::HashSet;
+
+    #[test]
+    fn names_every_cell_once() {
+        let mut names: HashSet<String> = HashSet::new();
+        for index in 0..CELL_COUNT {
+            let cell = Cell::from_coordinates(&bitboard::get_cell_coordinates(index)).unwrap();
+            let notation = cell.to_notation();
+            assert_eq!(Cell::from_notation(notation.as_str()), Some(cell), "{}", notation);
+            assert_eq!(Cell::from_notation(notation.to_uppercase().as_str()), Some(cell), "{}", notation);
+            names.insert(notation);
+        }
+        assert_eq!(names.len(), CELL_COUNT);
+        assert!(names.contains("a1") && names.contains("f11") && names.contains("l1") && names.contains("a6") && names.contains("l6"));
+    }
+
+    #[test]
+    fn rejects_cells_off_the_board() {
+        for notation in ["", "f", "5", "j5", "a0", "a7", "f12", "g11", "l7", "m1", "f-1", "ff5"] {
+            assert_eq!(Cell::from_notation(notation), None, "{}", notation);
+        }
+        assert_eq!(Cell::new(0, 0), None);
+        assert_eq!(Cell::new(11, 5), None);
+    }
+}
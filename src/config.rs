@@ -1,36 +1,137 @@
-use serde::Deserialize;
-use toml;
+use crate::pieces::PieceColor;
+use crate::scoring::{self, ScoringRule};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     pub board_from_file: bool,
     pub board_path: String,
+    /// Board entries in the board file format. Used instead of `board_path` when set, as wasm has no filesystem.
+    pub board: String,
+    /// "Glinski" or "McCooey", deciding how results are scored.
+    pub variant: String,
+    pub time_control: Option<TimeControl>,
+    /// "white" or "black".
+    pub starting_side: String,
+}
+
+/// Clock settings for each side, e.g. 300 seconds plus a 5 second increment per move.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TimeControl {
+    pub initial_seconds: u32,
+    #[serde(default)]
+    pub increment_seconds: u32,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            board_from_file: false,
+            board_path: "".to_string(),
+            board: "".to_string(),
+            variant: "Glinski".to_string(),
+            time_control: None,
+            starting_side: "white".to_string(),
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn get_scoring_rule(&self) -> Option<ScoringRule> {
+        scoring::get_scoring_rule(self.variant.as_str())
+    }
+
+    pub fn get_starting_side(&self) -> Option<PieceColor> {
+        match self.starting_side.to_lowercase().as_str() {
+            "white" | "w" => Some(PieceColor::White),
+            "black" | "b" => Some(PieceColor::Black),
+            _ => None
+        }
+    }
 }
 
 pub fn load_config() -> Result<AppConfig, Box<dyn std::error::Error>> {
     let config_path = "config.toml";
 
     if let Ok(config_content) = std::fs::read_to_string(config_path) {
-        let config: AppConfig = toml::from_str(&config_content)?;
-
-        if validate_config(&config) {
-            Ok(config)
-        } else {
-            get_fallback_config()
-        }
+        parse_config(&config_content)
     } else {
         get_fallback_config()
     }
 }
 
-fn validate_config(config: &AppConfig) -> bool {
-    !config.board_path.is_empty()
+/// Reads a config from the contents of a config.toml. Missing keys take their default values.
+pub fn parse_config(config_content: &str) -> Result<AppConfig, Box<dyn std::error::Error>> {
+    let config: AppConfig = toml::from_str(config_content)?;
+    validate_config(&config)?;
+    Ok(config)
+}
+
+fn validate_config(config: &AppConfig) -> Result<(), String> {
+    if config.board_from_file && config.board_path.is_empty() && config.board.is_empty() {
+        return Err("board_from_file is set but neither board_path nor board is given".to_string());
+    }
+    if config.get_scoring_rule().is_none() {
+        return Err(format!("Unknown variant \"{}\"", config.variant));
+    }
+    if config.get_starting_side().is_none() {
+        return Err(format!("Starting side must be \"white\" or \"black\", found \"{}\"", config.starting_side));
+    }
+    Ok(())
 }
 
 fn get_fallback_config() -> Result<AppConfig, Box<dyn std::error::Error>> {
-    let fallback_config: AppConfig = AppConfig {
-        board_from_file: false,
-        board_path: "".to_string(),
-    };
-    Ok(fallback_config)   
-}
\ No newline at end of file
+    Ok(AppConfig::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_full_config() {
+        let config = parse_config(r#"
+            board_from_file = true
+            board = "164:WK;20:BK"
+            variant = "McCooey"
+            starting_side = "black"
+
+            [time_control]
+            initial_seconds = 300
+            increment_seconds = 5
+        "#).unwrap();
+        assert!(config.board_from_file);
+        assert_eq!(config.board, "164:WK;20:BK");
+        assert_eq!(config.get_scoring_rule(), Some(ScoringRule::McCooey));
+        assert_eq!(config.get_starting_side(), Some(PieceColor::Black));
+        assert_eq!(config.time_control, Some(TimeControl { initial_seconds: 300, increment_seconds: 5 }));
+    }
+
+    #[test]
+    fn missing_keys_take_defaults() {
+        let config = parse_config("").unwrap();
+        assert!(!config.board_from_file);
+        assert_eq!(config.get_scoring_rule(), Some(ScoringRule::Glinski));
+        assert_eq!(config.get_starting_side(), Some(PieceColor::White));
+        assert_eq!(config.time_control, None);
+
+        let config = parse_config("[time_control]\ninitial_seconds = 60").unwrap();
+        assert_eq!(config.time_control, Some(TimeControl { initial_seconds: 60, increment_seconds: 0 }));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        for text in [
+            "board_from_file = \"yes\"",
+            "board_from_file = true",
+            "variant = \"Shafran\"",
+            "starting_side = \"red\"",
+            "[time_control]\nincrement_seconds = 5",
+            "[time_control]\ninitial_seconds = -1",
+            "variant = "
+        ] {
+            assert!(parse_config(text).is_err(), "{}", text);
+        }
+    }
+}
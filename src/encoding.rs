@@ -0,0 +1,332 @@
+use crate::bitboard::{self, Bitboard, CELL_COUNT};
+use crate::board::{self, Board};
+use crate::moves::{self, Move};
+use crate::pieces::{ChessPiece, PieceColor, PieceType};
+use crate::position::{self, Position};
+use std::fmt;
+
+/// Version written in the first byte. Bump it whenever the layout changes.
+pub const ENCODING_VERSION: u8 = 1;
+
+/// Set when the game doesn't start from the standard position, which is then stored in full.
+const FLAG_CUSTOM_POSITION: u8 = 0b001;
+const FLAG_BLACK_TO_MOVE: u8 = 0b010;
+const FLAG_EN_PASSANT: u8 = 0b100;
+
+/// Reasons a byte string can't be decoded into a game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EncodingError {
+    /// The bytes ended in the middle of the game.
+    Truncated,
+    /// The first byte isn't a version this build can read.
+    UnsupportedVersion(u8),
+    /// The nibble doesn't stand for a piece.
+    UnknownPiece(u8),
+    /// The en passant byte isn't a cell on the board.
+    BadEnPassant(u8),
+    /// The move (counted from 1) points past the end of the legal move list.
+    BadMoveIndex { move_number: usize, index: usize },
+    /// There are bytes left over after the last move.
+    TrailingBytes(usize),
+    /// The move (counted from 1) given to `encode_game` isn't legal in its position.
+    IllegalMove { ply: usize }
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodingError::Truncated => write!(f, "The encoded game is truncated"),
+            EncodingError::UnsupportedVersion(version) => write!(f, "Unsupported encoding version {}", version),
+            EncodingError::UnknownPiece(code) => write!(f, "Unknown piece code {}", code),
+            EncodingError::BadEnPassant(code) => write!(f, "Bad en passant cell {:#04x}", code),
+            EncodingError::BadMoveIndex { move_number, index } => {
+                write!(f, "Move {} has index {}, past the end of the legal moves", move_number, index)
+            }
+            EncodingError::TrailingBytes(count) => write!(f, "{} unexpected bytes after the last move", count),
+            EncodingError::IllegalMove { ply } => write!(f, "Move {} isn't legal and can't be encoded", ply)
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// Returns every legal move of `color` in a fixed order, with one entry per promotion choice.
+///
+/// Moves are sorted by origin, then destination, then promotion piece, so both ends of an encoding agree on the indices.
+pub fn get_sorted_legal_moves(board: &Board, color: PieceColor, en_passant: Option<(usize, usize)>) -> Vec<Move> {
+    let mut sorted_moves: Vec<Move> = Vec::new();
+    for move_info in Bitboard::from_board(board).get_legal_moves(color, en_passant) {
+        if moves::is_promotion_move(board, &move_info.from, &move_info.to) {
+            for promotion in moves::PROMOTION_PIECES {
+                sorted_moves.push(Move { promotion: Some(promotion), ..move_info });
+            }
+        } else {
+            sorted_moves.push(move_info);
+        }
+    }
+    sorted_moves.sort_by_key(|move_info| (move_info.from, move_info.to, get_promotion_order(move_info.promotion)));
+    sorted_moves
+}
+
+fn get_promotion_order(promotion: Option<PieceType>) -> usize {
+    promotion.and_then(|piece| moves::PROMOTION_PIECES.iter().position(|choice| *choice == piece)).unwrap_or(0)
+}
+
+/// Encodes a game as its start position and one index per move into `get_sorted_legal_moves`.
+///
+/// The layout is a version byte, a flags byte, the start position if it isn't the standard one (91 cells packed
+/// two to a byte, then the en passant cell and both counters), the number of moves and finally the move indices.
+/// An index takes one byte, or two when more than 256 moves were legal. Every move must be legal in turn.
+pub fn encode_game(start_position: &Position, game_moves: &[Move]) -> Result<Vec<u8>, EncodingError> {
+    let mut bytes = vec![ENCODING_VERSION];
+
+    let mut flags = 0;
+    if *start_position != position::get_default_position() {
+        flags |= FLAG_CUSTOM_POSITION;
+        if start_position.next_turn == PieceColor::Black {
+            flags |= FLAG_BLACK_TO_MOVE;
+        }
+        if start_position.en_passant.is_some() {
+            flags |= FLAG_EN_PASSANT;
+        }
+    }
+    bytes.push(flags);
+
+    if flags & FLAG_CUSTOM_POSITION != 0 {
+        write_cells(&start_position.board, &mut bytes);
+        if let Some(en_passant) = start_position.en_passant {
+            bytes.push(board::encode_coordinates_bitwise(&en_passant) as u8);
+        }
+        write_varint(start_position.halfmove_clock as u64, &mut bytes);
+        write_varint(start_position.fullmove_number as u64, &mut bytes);
+    }
+
+    write_varint(game_moves.len() as u64, &mut bytes);
+    let mut current = *start_position;
+    for (ply, move_info) in game_moves.iter().enumerate() {
+        let legal_moves = get_sorted_legal_moves(&current.board, current.next_turn, current.en_passant);
+        let move_info = Move { promotion: moves::get_promotion(move_info), ..*move_info };
+        let index = legal_moves.iter()
+            .position(|legal_move| *legal_move == move_info)
+            .ok_or(EncodingError::IllegalMove { ply: ply + 1 })?;
+        write_index(index, legal_moves.len(), &mut bytes);
+        position::play_move(&mut current, &move_info);
+    }
+
+    Ok(bytes)
+}
+
+/// Decodes a game written by `encode_game` into its start position and moves.
+pub fn decode_game(bytes: &[u8]) -> Result<(Position, Vec<Move>), EncodingError> {
+    let mut reader = bytes.iter().copied();
+    let version = reader.next().ok_or(EncodingError::Truncated)?;
+    if version != ENCODING_VERSION {
+        return Err(EncodingError::UnsupportedVersion(version));
+    }
+    let flags = reader.next().ok_or(EncodingError::Truncated)?;
+
+    let mut start_position = position::get_default_position();
+    if flags & FLAG_CUSTOM_POSITION != 0 {
+        start_position.board = read_cells(&mut reader)?;
+        start_position.next_turn = if flags & FLAG_BLACK_TO_MOVE != 0 { PieceColor::Black } else { PieceColor::White };
+        start_position.en_passant = None;
+        if flags & FLAG_EN_PASSANT != 0 {
+            let code = reader.next().ok_or(EncodingError::Truncated)?;
+            let en_passant = board::decode_coordinates_bitwise(code as usize);
+            if !moves::is_coordinate_in_bounds(&en_passant) {
+                return Err(EncodingError::BadEnPassant(code));
+            }
+            start_position.en_passant = Some(en_passant);
+        }
+        start_position.halfmove_clock = read_varint(&mut reader)? as u32;
+        start_position.fullmove_number = read_varint(&mut reader)? as u32;
+    }
+
+    let move_count = read_varint(&mut reader)? as usize;
+    let mut game_moves: Vec<Move> = Vec::new();
+    let mut current = start_position;
+    for move_number in 1..=move_count {
+        let legal_moves = get_sorted_legal_moves(&current.board, current.next_turn, current.en_passant);
+        let index = read_index(legal_moves.len(), &mut reader)?;
+        let move_info = *legal_moves.get(index).ok_or(EncodingError::BadMoveIndex { move_number, index })?;
+        position::play_move(&mut current, &move_info);
+        game_moves.push(move_info);
+    }
+
+    match reader.count() {
+        0 => Ok((start_position, game_moves)),
+        count => Err(EncodingError::TrailingBytes(count))
+    }
+}
+
+/// Returns the cells of the board in a fixed order, row by row.
+fn get_board_cells() -> impl Iterator<Item = (usize, usize)> {
+    (0..CELL_COUNT).map(bitboard::get_cell_coordinates)
+}
+
+/// Packs a piece into a nibble: the low three bits are the type (0 for an empty cell) and the high bit is set for Black.
+fn encode_piece(cell: &Option<ChessPiece>) -> u8 {
+    let Some(piece) = cell else { return 0 };
+    let piece_type = match piece.piece_type {
+        PieceType::Pawn => 1,
+        PieceType::Knight => 2,
+        PieceType::Bishop => 3,
+        PieceType::Rook => 4,
+        PieceType::Queen => 5,
+        PieceType::King => 6,
+        PieceType::None => return 0
+    };
+    match piece.color {
+        PieceColor::White => piece_type,
+        PieceColor::Black => piece_type | 0b1000,
+        PieceColor::None => 0
+    }
+}
+
+fn decode_piece(code: u8) -> Result<ChessPiece, EncodingError> {
+    let piece_type = match code & 0b0111 {
+        0 if code == 0 => return Ok(ChessPiece { piece_type: PieceType::None, color: PieceColor::None }),
+        1 => PieceType::Pawn,
+        2 => PieceType::Knight,
+        3 => PieceType::Bishop,
+        4 => PieceType::Rook,
+        5 => PieceType::Queen,
+        6 => PieceType::King,
+        _ => return Err(EncodingError::UnknownPiece(code))
+    };
+    let color = if code & 0b1000 != 0 { PieceColor::Black } else { PieceColor::White };
+    Ok(ChessPiece { piece_type, color })
+}
+
+fn write_cells(board: &Board, bytes: &mut Vec<u8>) {
+    let codes: Vec<u8> = get_board_cells().map(|(x, y)| encode_piece(&board.0[x][y])).collect();
+    for pair in codes.chunks(2) {
+        bytes.push(pair[0] << 4 | pair.get(1).copied().unwrap_or(0));
+    }
+}
+
+fn read_cells(reader: &mut impl Iterator<Item = u8>) -> Result<Board, EncodingError> {
+    let mut bitboard = Bitboard::default();
+    for index in (0..CELL_COUNT).step_by(2) {
+        let byte = reader.next().ok_or(EncodingError::Truncated)?;
+        bitboard.set_piece(index, decode_piece(byte >> 4)?);
+        if index + 1 < CELL_COUNT {
+            bitboard.set_piece(index + 1, decode_piece(byte & 0x0F)?);
+        }
+    }
+    Ok(bitboard.to_board())
+}
+
+fn write_index(index: usize, move_count: usize, bytes: &mut Vec<u8>) {
+    if move_count > 256 {
+        bytes.extend_from_slice(&(index as u16).to_le_bytes());
+    } else {
+        bytes.push(index as u8);
+    }
+}
+
+fn read_index(move_count: usize, reader: &mut impl Iterator<Item = u8>) -> Result<usize, EncodingError> {
+    let low = reader.next().ok_or(EncodingError::Truncated)? as usize;
+    if move_count > 256 {
+        let high = reader.next().ok_or(EncodingError::Truncated)? as usize;
+        return Ok(high << 8 | low);
+    }
+    Ok(low)
+}
+
+/// Writes a number seven bits at a time, lowest bits first, with the high bit set on all but the last byte.
+fn write_varint(mut value: u64, bytes: &mut Vec<u8>) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(reader: &mut impl Iterator<Item = u8>) -> Result<u64, EncodingError> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = reader.next().ok_or(EncodingError::Truncated)?;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(EncodingError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::RandomGame;
+
+    #[test]
+    fn round_trips_standard_games() {
+        let start_position = position::get_default_position();
+        for seed in 0..8 {
+            let game_moves: Vec<Move> = RandomGame::new(start_position, seed).take(60).map(|(_, move_info)| move_info).collect();
+            let bytes = encode_game(&start_position, &game_moves).unwrap();
+            assert_eq!(bytes.len(), 3 + game_moves.len());
+            assert_eq!(decode_game(&bytes), Ok((start_position, game_moves)));
+        }
+    }
+
+    #[test]
+    fn round_trips_custom_positions() {
+        // A White Pawn one step from promotion, Black to move with an en passant cell and running counters.
+        let mut start_position = position::get_default_position();
+        start_position.board = board::new();
+        start_position.board.0[10][4] = Some(ChessPiece { piece_type: PieceType::King, color: PieceColor::White });
+        start_position.board.0[5][0] = Some(ChessPiece { piece_type: PieceType::King, color: PieceColor::Black });
+        start_position.board.0[1][8] = Some(ChessPiece { piece_type: PieceType::Pawn, color: PieceColor::White });
+        start_position.next_turn = PieceColor::Black;
+        start_position.en_passant = Some((7, 5));
+        start_position.halfmove_clock = 7;
+        start_position.fullmove_number = 300;
+        let game_moves = vec![
+            get_sorted_legal_moves(&start_position.board, PieceColor::Black, start_position.en_passant)[0]
+        ];
+        let bytes = encode_game(&start_position, &game_moves).unwrap();
+        assert_eq!(decode_game(&bytes), Ok((start_position, game_moves.clone())));
+
+        let mut after_black = start_position;
+        position::play_move(&mut after_black, &game_moves[0]);
+        let promotions: Vec<Move> = get_sorted_legal_moves(&after_black.board, PieceColor::White, None)
+            .into_iter()
+            .filter(|move_info| move_info.promotion.is_some())
+            .collect();
+        assert_eq!(promotions.len(), 4);
+        for promotion in promotions {
+            let game_moves = vec![game_moves[0], promotion];
+            let bytes = encode_game(&start_position, &game_moves).unwrap();
+            assert_eq!(decode_game(&bytes), Ok((start_position, game_moves)));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_encodings() {
+        assert_eq!(decode_game(&[]), Err(EncodingError::Truncated));
+        assert_eq!(decode_game(&[ENCODING_VERSION + 1, 0, 0]), Err(EncodingError::UnsupportedVersion(ENCODING_VERSION + 1)));
+        assert_eq!(decode_game(&[ENCODING_VERSION, 0, 1]), Err(EncodingError::Truncated));
+        assert_eq!(
+            decode_game(&[ENCODING_VERSION, 0, 1, 200]),
+            Err(EncodingError::BadMoveIndex { move_number: 1, index: 200 })
+        );
+        assert_eq!(decode_game(&[ENCODING_VERSION, 0, 0, 0]), Err(EncodingError::TrailingBytes(1)));
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let start_position = position::get_default_position();
+        let mut game_moves: Vec<Move> = RandomGame::new(start_position, 1).take(4).map(|(_, move_info)| move_info).collect();
+        let legal = game_moves[2];
+
+        // A piece staying on its own cell, then Black's previous move again on White's turn.
+        game_moves[2] = Move { to: legal.from, ..legal };
+        assert_eq!(encode_game(&start_position, &game_moves), Err(EncodingError::IllegalMove { ply: 3 }));
+        game_moves[2] = game_moves[1];
+        assert_eq!(encode_game(&start_position, &game_moves), Err(EncodingError::IllegalMove { ply: 3 }));
+        game_moves[2] = legal;
+        assert!(encode_game(&start_position, &game_moves).is_ok());
+    }
+}
@@ -0,0 +1,296 @@
+use crate::bitboard::{self, CELL_COUNT};
+use crate::board::Board;
+use crate::hex::Hex;
+use crate::moves::{self, MoveDirection};
+use crate::pieces::{self, ChessPiece, PieceColor, PieceType};
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// The centre cell f6, which the piece-cell tables measure distances from.
+const CENTRE: Hex = Hex { q: 0, r: 0 };
+
+/// Points per step closer to f6, by piece type (Pawn, Knight, Bishop, Rook, Queen, King).
+///
+/// A Knight in the centre reaches all 12 of its cells but only 4 from a corner, so it gains the most. The King's
+/// place is scored by `king_safety` instead.
+const CENTRALISATION_WEIGHTS: [i32; 6] = [2, 6, 3, 2, 2, 0];
+
+/// Points a Pawn gains per cell it has advanced up its file.
+const PAWN_ADVANCE_WEIGHT: i32 = 2;
+
+const PASSED_PAWN_BONUS: i32 = 20;
+/// Added to `PASSED_PAWN_BONUS` per cell the passed Pawn is closer to promoting than a Pawn at the bottom of the f file.
+const PASSED_PAWN_ADVANCE_BONUS: i32 = 10;
+const DOUBLED_PAWN_PENALTY: i32 = 15;
+const ISOLATED_PAWN_PENALTY: i32 = 12;
+
+/// Points per own Pawn next to the King, shielding it.
+const KING_SHIELD_BONUS: i32 = 10;
+/// Points lost per cell next to the King that the opponent attacks.
+const KING_ZONE_ATTACK_PENALTY: i32 = 8;
+
+/// Points per cell a piece can move to, by piece type. Pawns and the King aren't counted.
+const MOBILITY_WEIGHTS: [i32; 6] = [0, 4, 3, 2, 1, 0];
+
+/// Returns the value of a piece in centipawns.
+///
+/// The Rook moves in 6 directions instead of 4, so it is worth more than in orthodox chess, while each of the three
+/// Bishops only reaches the third of the board of its own cell colour.
+pub fn get_piece_value(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 300,
+        PieceType::Bishop => 250,
+        PieceType::Rook => 550,
+        PieceType::Queen => 950,
+        PieceType::King | PieceType::None => 0
+    }
+}
+
+/// Points of one term of the evaluation for each side. Higher is better for that side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Term {
+    pub white: i32,
+    pub black: i32
+}
+
+impl Term {
+    fn add(&mut self, color: PieceColor, points: i32) {
+        match color {
+            PieceColor::White => self.white += points,
+            PieceColor::Black => self.black += points,
+            PieceColor::None => {}
+        }
+    }
+
+    /// Returns how much better the term is for White than for Black.
+    pub fn get_difference(&self) -> i32 {
+        self.white - self.black
+    }
+}
+
+/// The terms the evaluation of a position is made of, to show why one side is better.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Evaluation {
+    pub material: Term,
+    pub piece_cells: Term,
+    pub pawn_structure: Term,
+    pub king_safety: Term,
+    pub mobility: Term
+}
+
+impl Evaluation {
+    /// Returns the evaluation in centipawns from White's point of view.
+    pub fn get_total(&self) -> i32 {
+        self.material.get_difference()
+            + self.piece_cells.get_difference()
+            + self.pawn_structure.get_difference()
+            + self.king_safety.get_difference()
+            + self.mobility.get_difference()
+    }
+}
+
+fn get_color_index(color: PieceColor) -> usize {
+    if color == PieceColor::Black { 1 } else { 0 }
+}
+
+fn get_piece_index(piece_type: PieceType) -> Option<usize> {
+    match piece_type {
+        PieceType::Pawn => Some(0),
+        PieceType::Knight => Some(1),
+        PieceType::Bishop => Some(2),
+        PieceType::Rook => Some(3),
+        PieceType::Queen => Some(4),
+        PieceType::King => Some(5),
+        PieceType::None => None
+    }
+}
+
+fn get_forward(color: PieceColor) -> Hex {
+    let direction = if color == PieceColor::Black { MoveDirection::Down } else { MoveDirection::Up };
+    moves::get_direction_offset(direction)
+}
+
+static CELL_TABLES: OnceLock<[[[i32; CELL_COUNT]; 6]; 2]> = OnceLock::new();
+
+/// Returns the piece-cell tables, indexed by colour (White, Black), piece type and cell index.
+///
+/// Every piece scores for being close to f6. Pawns also score for each cell they have advanced, counted from the
+/// back of their file, so both sides' tables mirror each other.
+fn get_cell_tables() -> &'static [[[i32; CELL_COUNT]; 6]; 2] {
+    CELL_TABLES.get_or_init(|| {
+        let mut tables = [[[0; CELL_COUNT]; 6]; 2];
+        for index in 0..CELL_COUNT {
+            let cell = Hex::from_coordinates(&bitboard::get_cell_coordinates(index));
+            let centralisation = 5 - cell.distance(&CENTRE);
+            for color in [PieceColor::White, PieceColor::Black] {
+                let table = &mut tables[get_color_index(color)];
+                for (piece_index, weight) in CENTRALISATION_WEIGHTS.iter().enumerate() {
+                    table[piece_index][index] = weight * centralisation;
+                }
+                let advanced = cell.get_ray(get_forward(pieces::get_opponent_color(color))).count() as i32;
+                table[0][index] += PAWN_ADVANCE_WEIGHT * advanced;
+            }
+        }
+        tables
+    })
+}
+
+/// Returns the piece-cell table entry of the piece on the cell index.
+fn get_cell_score(piece: ChessPiece, index: usize) -> i32 {
+    get_piece_index(piece.piece_type).map_or(0, |piece_index| get_cell_tables()[get_color_index(piece.color)][piece_index][index])
+}
+
+fn get_piece(board: &Board, cell: Hex) -> Option<ChessPiece> {
+    let (x, y) = cell.to_coordinates()?;
+    board.0[x][y].filter(|piece| piece.color != PieceColor::None && piece.piece_type != PieceType::None)
+}
+
+fn is_pawn(board: &Board, cell: Hex, color: PieceColor) -> bool {
+    get_piece(board, cell).is_some_and(|piece| piece.piece_type == PieceType::Pawn && piece.color == color)
+}
+
+/// Checks if no opponent Pawn stands in front of the Pawn on its file or can capture onto a cell in front of it.
+fn is_passed_pawn(board: &Board, cell: Hex, color: PieceColor) -> bool {
+    let opponent = pieces::get_opponent_color(color);
+    !cell.get_ray(get_forward(color)).any(|ahead| is_pawn(board, ahead, opponent) || moves::is_attacked_by_pawn(board, ahead, opponent))
+}
+
+/// Scores passed, doubled and isolated Pawns. Files are the columns of cells sharing a letter, like f1 to f11.
+fn get_pawn_structure(board: &Board, pawns: &[(Hex, PieceColor)]) -> Term {
+    let mut term = Term::default();
+    let mut file_counts = [[0; 11]; 2];
+    for (cell, color) in pawns {
+        file_counts[get_color_index(*color)][(cell.q + 5) as usize] += 1;
+    }
+
+    for (cell, color) in pawns {
+        if is_passed_pawn(board, *cell, *color) {
+            let cells_to_promotion = cell.get_ray(get_forward(*color)).count() as i32;
+            term.add(*color, PASSED_PAWN_BONUS + PASSED_PAWN_ADVANCE_BONUS * (10 - cells_to_promotion));
+        }
+        let counts = &file_counts[get_color_index(*color)];
+        let file = (cell.q + 5) as usize;
+        let has_neighbour = (file > 0 && counts[file - 1] > 0) || counts.get(file + 1).is_some_and(|count| *count > 0);
+        if !has_neighbour {
+            term.add(*color, -ISOLATED_PAWN_PENALTY);
+        }
+    }
+
+    for (color, counts) in [PieceColor::White, PieceColor::Black].iter().zip(file_counts) {
+        for count in counts {
+            if count > 1 {
+                term.add(*color, -DOUBLED_PAWN_PENALTY * (count - 1));
+            }
+        }
+    }
+    term
+}
+
+/// Scores the King's own Pawns around it and the cells around it the opponent attacks.
+fn get_king_safety(board: &Board, king: Hex, color: PieceColor) -> i32 {
+    let opponent = pieces::get_opponent_color(color);
+    let mut points = 0;
+    for cell in king.get_orthogonal_neighbours().chain(king.get_diagonal_neighbours()) {
+        if is_pawn(board, cell, color) {
+            points += KING_SHIELD_BONUS;
+        }
+        if let Some(coordinates) = cell.to_coordinates() {
+            if moves::is_square_attacked(board, &coordinates, opponent) {
+                points -= KING_ZONE_ATTACK_PENALTY;
+            }
+        }
+    }
+    points
+}
+
+/// Breaks the evaluation of the board down into its terms.
+pub fn get_evaluation(board: &Board) -> Evaluation {
+    let mut evaluation = Evaluation::default();
+    let mut pawns: Vec<(Hex, PieceColor)> = Vec::new();
+
+    for index in 0..CELL_COUNT {
+        let coordinates = bitboard::get_cell_coordinates(index);
+        let cell = Hex::from_coordinates(&coordinates);
+        let Some(piece) = get_piece(board, cell) else { continue };
+        let Some(piece_index) = get_piece_index(piece.piece_type) else { continue };
+
+        evaluation.material.add(piece.color, get_piece_value(piece.piece_type));
+        evaluation.piece_cells.add(piece.color, get_cell_score(piece, index));
+        match piece.piece_type {
+            PieceType::Pawn => pawns.push((cell, piece.color)),
+            PieceType::King => evaluation.king_safety.add(piece.color, get_king_safety(board, cell, piece.color)),
+            _ => {
+                let mobility = moves::get_legal_moves(board, &coordinates, None).len() as i32;
+                evaluation.mobility.add(piece.color, MOBILITY_WEIGHTS[piece_index] * mobility);
+            }
+        }
+    }
+    evaluation.pawn_structure = get_pawn_structure(board, &pawns);
+    evaluation
+}
+
+/// Evaluates the board in centipawns from the point of view of `color`.
+pub fn evaluate(board: &Board, color: PieceColor) -> i32 {
+    let total = get_evaluation(board).get_total();
+    if color == PieceColor::Black { -total } else { total }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::{self, read_position};
+    use crate::test_support::RandomGame;
+
+    /// Reflects the board top to bottom and swaps the colours, which should negate the evaluation.
+    fn get_mirrored(board: &Board) -> Board {
+        let mut mirrored = crate::board::new();
+        for index in 0..CELL_COUNT {
+            let coordinates = bitboard::get_cell_coordinates(index);
+            let cell = Hex::from_coordinates(&coordinates);
+            let (x, y) = Hex::new(cell.q, cell.get_s()).to_coordinates().unwrap();
+            mirrored.0[x][y] = board.0[coordinates.0][coordinates.1].map(|piece| ChessPiece {
+                piece_type: piece.piece_type,
+                color: pieces::get_opponent_color(piece.color)
+            });
+        }
+        mirrored
+    }
+
+    #[test]
+    fn starting_position_is_even() {
+        let evaluation = get_evaluation(&position::get_default_position().board);
+        assert_eq!(evaluation.material.white, 9 * 100 + 2 * 300 + 3 * 250 + 2 * 550 + 950);
+        assert_eq!(evaluation.get_total(), 0);
+    }
+
+    #[test]
+    fn mirrored_positions_evaluate_opposite() {
+        for seed in 0..6u64 {
+            for (current, _) in RandomGame::new(position::get_default_position(), seed).take(60) {
+                let evaluation = get_evaluation(&current.board);
+                let mirrored = get_evaluation(&get_mirrored(&current.board));
+                assert_eq!(mirrored.get_total(), -evaluation.get_total());
+                assert_eq!(evaluation.pawn_structure.white, mirrored.pawn_structure.black);
+            }
+        }
+    }
+
+    #[test]
+    fn scores_pawn_structure() {
+        // White has a passed, isolated Pawn on f7 and doubled, isolated Pawns on b1 and b2, blocked by the Black Pawn on b7.
+        let position = read_position("1/3/5/7/p3P4/11/11/11/11/1P9/1P3K3k1 w - 0 1").unwrap();
+        let white_pawn = |notation: &str| {
+            let (x, y) = crate::cell::Cell::from_notation(notation).unwrap().to_coordinates();
+            assert_eq!(position.board.0[x][y].unwrap().piece_type, PieceType::Pawn, "{}", notation);
+            Hex::from_coordinates(&(x, y))
+        };
+        assert!(is_passed_pawn(&position.board, white_pawn("f7"), PieceColor::White));
+        assert!(!is_passed_pawn(&position.board, white_pawn("b1"), PieceColor::White));
+
+        let structure = get_evaluation(&position.board).pawn_structure;
+        let passed = PASSED_PAWN_BONUS + PASSED_PAWN_ADVANCE_BONUS * (10 - 4);
+        assert_eq!(structure.white, passed - DOUBLED_PAWN_PENALTY - 3 * ISOLATED_PAWN_PENALTY);
+        assert_eq!(structure.black, -ISOLATED_PAWN_PENALTY);
+    }
+}
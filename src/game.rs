@@ -1,17 +1,59 @@
 use crate::pieces::PieceColor;
 use crate::pieces::ChessPiece;
+use crate::pieces::PieceType;
 use crate::board::Board;
-use crate::moves::Move;
+use crate::moves::UndoRecord;
+use serde::{Deserialize, Serialize};
 
-pub type History = Vec<Move>;
+/// A move that has been played, along with what it did to the Game.
+/// 
+/// 1. `undo`: The move itself with the piece it captured, as returned by `moves::make_move` to take the move back.
+/// 2. `previous_en_passant`: The en passant target before the move, restored when the move is taken back.
+/// 3. `previous_halfmove_clock`: The halfmove clock before the move, restored when the move is taken back.
+/// 4. `promotion`: The piece a Pawn was promoted to, if the move was a promotion.
+/// 5. `check`: Whether the move put the opponent in Check.
+/// 6. `checkmate`: Whether the move Checkmated the opponent.
+/// 7. `san`: The move in Standard Algebraic Notation, e.g. "Qxb4+".
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MoveRecord {
+    pub undo: UndoRecord,
+    pub previous_en_passant: Option<(usize, usize)>,
+    pub previous_halfmove_clock: u32,
+    pub promotion: Option<PieceType>,
+    pub check: bool,
+    pub checkmate: bool,
+    pub san: String
+}
+
+pub type History = Vec<MoveRecord>;
+
+/// Number of halfmoves without a Pawn move or a capture after which the Game is drawn.
+pub const FIFTY_MOVE_RULE_HALFMOVES: u32 = 100;
 
-/// Struct that contains what the Game's current Result is. Can be Ongoing, Checkmate, Stalemate, Draw.
-#[derive(PartialEq, Debug)]
+/// Why a Game ended in a Draw.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum DrawReason {
+    /// The same position occurred for the third time.
+    ThreefoldRepetition,
+    /// Fifty moves by each side passed without a Pawn move or a capture.
+    FiftyMoveRule,
+    /// Neither side has enough pieces left to Checkmate.
+    InsufficientMaterial,
+    /// The players agreed to a Draw, as recorded by an imported game.
+    Agreement
+}
+
+/// Struct that contains what the Game's current Result is. Can be Ongoing, Checkmate, Stalemate, Draw, Resignation.
+/// 
+/// `Checkmate` holds the winning side, `Stalemate` holds the side that gave stalemate and `Draw` holds why it was drawn.
+/// `Resignation` holds the winning side of a game an imported record declares won without Checkmate on the board.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum GameResult {
     Ongoing,
-    Checkmate,
-    Stalemate,
-    Draw
+    Checkmate(PieceColor),
+    Stalemate(PieceColor),
+    Draw(DrawReason),
+    Resignation(PieceColor)
 }
 
 /// This struct is responsible for storing the Game's state.
@@ -19,7 +61,7 @@ pub enum GameResult {
 /// # Fields and their Meanings
 /// 
 /// 1. `id`: This ID will be useful for storing, identifying and loading games.
-/// 2. `move_history`: Vector of Moves. This will store all the moves that have been played in the game.
+/// 2. `move_history`: Vector of MoveRecords. This will store all the moves that have been played in the game.
 /// 3. `board`: This is the actual board itself. Contains all the places, and pieces.
 /// 4. `next_turn`: This will store which color will play next.
 /// 5. `captured_pieces`: Array of 2 Vectors, 1 for Black and 1 for White. Each vector will store what Pieces have Black and White sides captured.
@@ -31,4 +73,4 @@ pub struct Game {
     pub next_turn: PieceColor,
     pub captured_pieces: [Vec<ChessPiece>; 2],
     pub result: GameResult
-}
\ No newline at end of file
+}
@@ -0,0 +1,132 @@
+use std::ops::Add;
+
+/// Distance from the centre cell (f6) to the edge of the board.
+const BOARD_RADIUS: i32 = 5;
+
+/// Offsets to the 6 cells sharing an edge, in the order Up, RightUp, RightDown, Down, LeftDown, LeftUp.
+pub const ORTHOGONAL_DIRECTIONS: [Hex; 6] = [
+    Hex { q: 0, r: -1 }, Hex { q: -1, r: 0 }, Hex { q: -1, r: 1 },
+    Hex { q: 0, r: 1 }, Hex { q: 1, r: 0 }, Hex { q: 1, r: -1 },
+];
+
+/// Offsets to the 6 nearest cells of the same colour, in the order DiagonalRightUp, DiagonalRight,
+/// DiagonalRightDown, DiagonalLeftDown, DiagonalLeft, DiagonalLeftUp.
+pub const DIAGONAL_DIRECTIONS: [Hex; 6] = [
+    Hex { q: -1, r: -1 }, Hex { q: -2, r: 1 }, Hex { q: -1, r: 2 },
+    Hex { q: 1, r: 1 }, Hex { q: 2, r: -1 }, Hex { q: 1, r: -2 },
+];
+
+/// Offsets of a Knight's jump: one orthogonal step followed by a diagonal step that doesn't point back.
+pub const KNIGHT_OFFSETS: [Hex; 12] = [
+    Hex { q: 1, r: -3 }, Hex { q: 2, r: -3 }, Hex { q: -1, r: -2 }, Hex { q: -2, r: -1 },
+    Hex { q: -3, r: 1 }, Hex { q: -3, r: 2 }, Hex { q: -2, r: 3 }, Hex { q: -1, r: 3 },
+    Hex { q: 1, r: 2 }, Hex { q: 2, r: 1 }, Hex { q: 3, r: -1 }, Hex { q: 3, r: -2 },
+];
+
+/// A cell in axial coordinates, centred on f6.
+///
+/// `q` grows with `y` (towards the "a" file) and `r` grows with `x` (towards White), so `Board.0[x][y]` is
+/// the cell `q = y - 5`, `r = x - 5`. The third cube coordinate `s` is `-q - r`, and a cell is on the board
+/// when none of the three is more than 5 away from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Hex {
+    pub q: i32,
+    pub r: i32
+}
+
+impl Hex {
+    pub fn new(q: i32, r: i32) -> Hex {
+        Hex { q, r }
+    }
+
+    pub fn from_coordinates(coordinates: &(usize, usize)) -> Hex {
+        Hex::new(coordinates.1 as i32 - BOARD_RADIUS, coordinates.0 as i32 - BOARD_RADIUS)
+    }
+
+    /// Returns the index into `Board.0`, or `None` if the cell isn't on the board.
+    pub fn to_coordinates(self) -> Option<(usize, usize)> {
+        if self.is_on_board() {
+            Some(((self.r + BOARD_RADIUS) as usize, (self.q + BOARD_RADIUS) as usize))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    pub fn is_on_board(&self) -> bool {
+        self.q.abs() <= BOARD_RADIUS && self.r.abs() <= BOARD_RADIUS && self.get_s().abs() <= BOARD_RADIUS
+    }
+
+    /// Number of orthogonal steps between two cells.
+    pub fn distance(&self, other: &Hex) -> i32 {
+        let difference = *other + Hex::new(-self.q, -self.r);
+        (difference.q.abs() + difference.r.abs() + difference.get_s().abs()) / 2
+    }
+
+    /// Returns the neighbouring cells on the board that share an edge with this one.
+    pub fn get_orthogonal_neighbours(self) -> impl Iterator<Item = Hex> {
+        ORTHOGONAL_DIRECTIONS.into_iter().map(move |direction| self + direction).filter(Hex::is_on_board)
+    }
+
+    /// Returns the nearest cells on the board along the diagonals, which touch this one only at a corner.
+    pub fn get_diagonal_neighbours(self) -> impl Iterator<Item = Hex> {
+        DIAGONAL_DIRECTIONS.into_iter().map(move |direction| self + direction).filter(Hex::is_on_board)
+    }
+
+    /// Returns the cells from this one (exclusive) towards `direction` until the edge of the board.
+    pub fn get_ray(self, direction: Hex) -> impl Iterator<Item = Hex> {
+        std::iter::successors(Some(self + direction), move |cell| Some(*cell + direction))
+            .take_while(Hex::is_on_board)
+    }
+}
+
+impl Add for Hex {
+    type Output = Hex;
+
+    fn add(self, other: Hex) -> Hex {
+        Hex::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+
+    fn get_hex(notation: &str) -> Hex {
+        Hex::from_coordinates(&Cell::from_notation(notation).unwrap().to_coordinates())
+    }
+
+    #[test]
+    fn measures_distances_across_the_board() {
+        let centre = get_hex("f6");
+        assert_eq!(centre, Hex::new(0, 0));
+        for corner in ["a1", "a6", "f11", "l6", "l1", "f1"] {
+            assert_eq!(centre.distance(&get_hex(corner)), BOARD_RADIUS, "{}", corner);
+        }
+        assert_eq!(get_hex("a1").distance(&get_hex("l1")), 10);
+        assert_eq!(get_hex("a1").distance(&get_hex("f11")), 10);
+        assert_eq!(get_hex("a1").distance(&get_hex("a1")), 0);
+    }
+
+    #[test]
+    fn stops_at_the_edge_of_the_board() {
+        assert_eq!(get_hex("f6").get_orthogonal_neighbours().count(), 6);
+        assert_eq!(get_hex("f6").get_diagonal_neighbours().count(), 6);
+        assert_eq!(get_hex("a1").get_orthogonal_neighbours().count(), 3);
+        assert_eq!(get_hex("a1").get_diagonal_neighbours().count(), 2);
+        assert_eq!(get_hex("a2").get_orthogonal_neighbours().count(), 4);
+        assert!(get_hex("a2").get_orthogonal_neighbours().all(|hex| hex.distance(&get_hex("a2")) == 1));
+
+        let ray: Vec<Hex> = get_hex("f1").get_ray(ORTHOGONAL_DIRECTIONS[0]).collect();
+        assert_eq!(ray.len(), 10);
+        assert_eq!(ray.last(), Some(&get_hex("f11")));
+        assert_eq!(get_hex("f1").get_ray(DIAGONAL_DIRECTIONS[0]).count(), 5);
+        assert_eq!(get_hex("a1").get_ray(ORTHOGONAL_DIRECTIONS[4]).count(), 0);
+        assert_eq!(get_hex("f11").get_ray(ORTHOGONAL_DIRECTIONS[0]).count(), 0);
+        assert_eq!(Hex::new(6, 0).to_coordinates(), None);
+    }
+}
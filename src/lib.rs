@@ -3,43 +3,181 @@ mod board;
 mod moves;
 mod game;
 mod validation;
+mod scoring;
+mod cell;
+mod san;
+mod pgn;
+mod position;
+mod config;
+mod encoding;
+mod hex;
+mod bitboard;
+mod zobrist;
+mod search;
+mod eval;
+#[cfg(test)]
+mod test_support;
 
 use crate::moves::Move;
 use crate::pieces::PieceColor;
 use crate::pieces::ChessPiece;
 use crate::board::Board;
 use crate::game::History;
+use crate::game::MoveRecord;
 use crate::game::GameResult;
+use crate::game::DrawReason;
 use crate::pieces::PieceType;
 use crate::moves::MoveList;
+use crate::scoring::ScoringRule;
+use crate::validation::MoveError;
+use crate::cell::Cell;
+use crate::position::Position;
+use crate::config::{AppConfig, TimeControl};
 
 use wasm_bindgen::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use serde_json;
 
+/// Version of the JSON written by `Game::save`. Bump it whenever the saved fields change.
+const SAVE_FORMAT_VERSION: u32 = 2;
+
 #[wasm_bindgen]
+#[derive(Serialize, Deserialize)]
 pub struct Game {
     id: u32,
     move_history: History,
     board: Board,
     current_turn: PieceColor,
+    en_passant: Option<(usize, usize)>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    start_position: Position,
     captured_pieces: [Vec<ChessPiece>; 2],
-    result: GameResult
+    redo_history: History,
+    position_history: Vec<u64>,
+    claim_based_draws: bool,
+    use_notation: bool,
+    result: GameResult,
+    scoring_rule: ScoringRule,
+    time_control: Option<TimeControl>,
+    tags: Vec<(String, String)>
 }
 
 #[wasm_bindgen]
 impl Game {
-    pub fn new() -> Game {
-        Game {
-            id: 0,
-            move_history: Vec::new(),
-            board: board::get_default_board(),
-            current_turn: PieceColor::White,
-            captured_pieces: [Vec::new(), Vec::new()],
-            result: GameResult::Ongoing
+    /// Starts a game as set up by config.toml, or the standard game if there is no config.toml.
+    /// A config.toml that can't be used, e.g. because its board file is missing, is an error.
+    pub fn new() -> Result<Game, JsValue> {
+        config::load_config()
+            .and_then(|config| Game::new_from_config(&config))
+            .map_err(|error| JsValue::from_str(format!("config.toml: {}", error).as_str()))
+    }
+
+    /// Starts a game from the contents of a config.toml, for builds without a filesystem.
+    pub fn from_config(config: String) -> Result<Game, JsValue> {
+        config::parse_config(config.as_str())
+            .and_then(|config| Game::new_from_config(&config))
+            .map_err(|error| JsValue::from_str(error.to_string().as_str()))
+    }
+
+    /// Returns the full game state as JSON, including history, captured pieces and tags, to be restored with `load`.
+    pub fn save(&self) -> String {
+        serde_json::json!({
+            "version": SAVE_FORMAT_VERSION,
+            "game": self
+        }).to_string()
+    }
+
+    /// Restores a game written by `save`.
+    pub fn load(save: String) -> Result<Game, JsValue> {
+        Game::read_save(save.as_str()).map_err(|error| JsValue::from_str(error.to_string().as_str()))
+    }
+
+    /// Returns the start position and moves in the compact binary format of the `encoding` module.
+    /// Tags, the scoring rule and claimed draws aren't included.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsValue> {
+        let game_moves: Vec<Move> = self.move_history.iter().map(|record| record.undo.move_info).collect();
+        encoding::encode_game(&self.start_position, &game_moves).map_err(|error| JsValue::from_str(error.to_string().as_str()))
+    }
+
+    /// Replays a game written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Game, JsValue> {
+        Game::read_bytes(bytes).map_err(|error| JsValue::from_str(error.to_string().as_str()))
+    }
+
+    /// Returns the Zobrist hash of the current position as 16 hex digits. Equal positions, with the same side to move
+    /// and en passant cell, have equal hashes.
+    pub fn get_position_hash(&self) -> String {
+        format!("{:016x}", zobrist::get_hash(&self.board, self.current_turn, self.en_passant))
+    }
+
+    /// Searches the current position to `depth` moves (at most 32) for the computer's move, as JSON like
+    /// {"from":"f5","to":"f6","promotion":""} ready for `make_move_with_promotion`, or null when the game is over.
+    pub fn best_move_depth(&self, depth: u32) -> JsValue {
+        self.get_best_move_json(search::SearchLimit::Depth(depth))
+    }
+
+    /// Same as `best_move_depth`, but searches as deep as it can in `time_ms` milliseconds.
+    pub fn best_move_time(&self, time_ms: u32) -> JsValue {
+        self.get_best_move_json(search::SearchLimit::Time(time_ms))
+    }
+
+    fn get_best_move_json(&self, limit: search::SearchLimit) -> JsValue {
+        match self.find_best_move(limit) {
+            Some(move_info) => {
+                let promotion = match move_info.promotion {
+                    Some(PieceType::Queen) => "Q",
+                    Some(PieceType::Rook) => "R",
+                    Some(PieceType::Bishop) => "B",
+                    Some(PieceType::Knight) => "N",
+                    _ => ""
+                };
+                JsValue::from_str(serde_json::json!({
+                    "from": self.format_coordinate(&move_info.from),
+                    "to": self.format_coordinate(&move_info.to),
+                    "promotion": promotion
+                }).to_string().as_str())
+            },
+            None => JsValue::NULL
+        }
+    }
+
+    /// Returns the engine's evaluation of the current position, split into the terms it is made of, as JSON like
+    /// {"material":{"white":4300,"black":4200},...,"total":112}. Points are in centipawns and the total is from White's
+    /// point of view, positive when White is better.
+    pub fn get_evaluation(&self) -> String {
+        let evaluation = eval::get_evaluation(&self.board);
+        let mut json = serde_json::to_value(evaluation).unwrap();
+        json["total"] = serde_json::json!(evaluation.get_total());
+        json.to_string()
+    }
+
+    /// Returns the configured time control as JSON, e.g. {"initial_seconds":300,"increment_seconds":5},
+    /// or null for untimed games.
+    pub fn get_time_control(&self) -> JsValue {
+        match self.time_control {
+            Some(time_control) => JsValue::from_str(serde_json::json!({
+                "initial_seconds": time_control.initial_seconds,
+                "increment_seconds": time_control.increment_seconds
+            }).to_string().as_str()),
+            None => JsValue::NULL
         }
     }
 
+    /// Starts a game from a single line position, as returned by `to_position`.
+    pub fn from_position(position: String) -> Result<Game, JsValue> {
+        position::read_position(position.as_str())
+            .map(Game::new_from_position)
+            .map_err(|error| JsValue::from_str(error.to_string().as_str()))
+    }
+
+    /// Returns the current position on a single line: the pieces rank by rank, side to move, en passant cell,
+    /// halfmove clock and fullmove number.
+    pub fn to_position(&self) -> String {
+        position::write_position(&self.get_position())
+    }
+
     fn convert_piece_id_to_icon(id: String) -> String {
         match id.as_str() {
             "WP" => String::from("♙"),
@@ -58,30 +196,33 @@ impl Game {
         }
     }
 
+    /// Returns the two letter ID of a piece, e.g. "WP" for a White Pawn.
+    fn get_piece_id(piece: &ChessPiece) -> String {
+        let piece_type = match piece.piece_type {
+            pieces::PieceType::Pawn => "P",
+            pieces::PieceType::Rook => "R",
+            pieces::PieceType::Knight => "N",
+            pieces::PieceType::Bishop => "B",
+            pieces::PieceType::Queen => "Q",
+            pieces::PieceType::King => "K",
+            pieces::PieceType::None => ""
+        };
+        let piece_color = match piece.color {
+            pieces::PieceColor::White => "W",
+            pieces::PieceColor::Black => "B",
+            pieces::PieceColor::None => ""
+        };
+        format!("{}{}", piece_color, piece_type)
+    }
+
     pub fn get_board(&self) -> JsValue {
         let mut board: HashMap<String, String> = HashMap::new();
         for i in 0usize..11usize {
             for j in 0usize..11usize {
                 if let Some(piece) = self.board.0[i][j] {
                     if (piece.color != PieceColor::None) && (piece.piece_type != PieceType::None) {
-                        let piece_type = match piece.piece_type {
-                            pieces::PieceType::Pawn => "P",
-                            pieces::PieceType::Rook => "R",
-                            pieces::PieceType::Knight => "N",
-                            pieces::PieceType::Bishop => "B",
-                            pieces::PieceType::Queen => "Q",
-                            pieces::PieceType::King => "K",
-                            pieces::PieceType::None => ""
-                        };
-                        let piece_color = match piece.color {
-                            pieces::PieceColor::White => "W",
-                            pieces::PieceColor::Black => "B",
-                            pieces::PieceColor::None => ""
-                        };
-                        let mut piece_string = format!("{}{}", piece_color, piece_type);
-                        piece_string = Self::convert_piece_id_to_icon(piece_string);
-                        let coordinate_string = format!("{},{}", i, j);
-                        board.insert(coordinate_string, piece_string);
+                        let piece_string = Self::convert_piece_id_to_icon(Self::get_piece_id(&piece));
+                        board.insert(self.format_coordinate(&(i, j)), piece_string);
                     }
                 }
             }
@@ -97,51 +238,283 @@ impl Game {
         }
     }
 
-    pub fn get_current_player_moves(&self) -> JsValue {
-        let mut moves: HashMap<String, Vec<String>> = HashMap::new();
-        let all_pieces = pieces::get_all_pieces(&self.board, self.current_turn);
-        let mut move_list: MoveList = MoveList::new();
-        for piece in all_pieces {
-            let piece_moves = moves::get_legal_moves(&self.board, &(piece.x, piece.y));
-            move_list.insert((piece.x, piece.y), piece_moves);
+    /// Switches the coordinates returned by the API between "x,y" strings and Glinski notation like "f5".
+    /// Moves are accepted in either form regardless.
+    pub fn set_use_notation(&mut self, enabled: bool) {
+        self.use_notation = enabled;
+    }
+
+    fn format_coordinate(&self, coordinates: &(usize, usize)) -> String {
+        match Cell::from_coordinates(coordinates) {
+            Some(cell) if self.use_notation => cell.to_notation(),
+            _ => format!("{},{}", coordinates.0, coordinates.1)
         }
-        moves::remove_check_moves(&self.board, &mut move_list);
+    }
+
+    fn move_list_to_json(&self, move_list: MoveList) -> JsValue {
+        let mut moves: HashMap<String, Vec<String>> = HashMap::new();
         for (coordinate, moves_internal) in move_list {
-            let coordinate_string = format!("{},{}", coordinate.0, coordinate.1);
+            let coordinate_string = self.format_coordinate(&coordinate);
             let mut move_strings: Vec<String> = Vec::new();
             for m in moves_internal {
-                let move_string = format!("{},{}", m.0, m.1);
-                move_strings.push(move_string);
+                move_strings.push(self.format_coordinate(&m));
             }
             moves.insert(coordinate_string, move_strings);
         }
         JsValue::from_str(serde_json::to_string(&moves).unwrap().as_str())
     }
 
-    pub fn make_move(&mut self, from: String, to: String) {
-        let from_split: Vec<&str> = from.split(",").collect();
-        let from_x: usize = from_split[0].parse().unwrap();
-        let from_y: usize = from_split[1].parse().unwrap();
-        
-        let to_split: Vec<&str> = to.split(",").collect();
-        let to_x: usize = to_split[0].parse().unwrap();
-        let to_y: usize = to_split[1].parse().unwrap();
+    pub fn get_current_player_moves(&self) -> JsValue {
+        let move_list = moves::get_all_legal_moves(&self.board, self.current_turn, self.en_passant);
+        self.move_list_to_json(move_list)
+    }
 
-        let piece = self.board.clone().0[from_x][from_y].unwrap();
+    /// Returns the subset of the current player's moves that promote a Pawn, so the UI can ask for a piece.
+    pub fn get_current_player_promotion_moves(&self) -> JsValue {
+        let mut move_list = moves::get_all_legal_moves(&self.board, self.current_turn, self.en_passant);
+        for (from, to_list) in move_list.iter_mut() {
+            to_list.retain(|to| moves::is_promotion_move(&self.board, from, to));
+        }
+        move_list.retain(|_, to_list| !to_list.is_empty());
+        self.move_list_to_json(move_list)
+    }
 
-        moves::execute_move(&mut self.board, &Move {
-            piece,
-            from: (from_x, from_y),
-            to: (to_x, to_y)
-        });
+    fn parse_promotion_piece(piece: &str) -> Result<Option<PieceType>, MoveError> {
+        match piece {
+            "" => Ok(None),
+            "Q" => Ok(Some(PieceType::Queen)),
+            "R" => Ok(Some(PieceType::Rook)),
+            "B" => Ok(Some(PieceType::Bishop)),
+            "N" => Ok(Some(PieceType::Knight)),
+            _ => Err(MoveError::InvalidPromotion(piece.to_string()))
+        }
     }
 
-    pub fn switch_player(&mut self) {
-        self.current_turn = match self.current_turn {
-            PieceColor::White => PieceColor::Black,
-            PieceColor::Black => PieceColor::White,
-            PieceColor::None => PieceColor::None
+    /// Validates and plays a move given as "x,y" coordinates or Glinski notation, then passes the turn to the other side.
+    /// 
+    /// A Pawn reaching the last cell of its file becomes a Queen; use `make_move_with_promotion` to choose.
+    pub fn make_move(&mut self, from: String, to: String) -> Result<(), JsValue> {
+        self.make_move_with_promotion(from, to, String::new())
+    }
+
+    /// Same as `make_move`, but a Pawn reaching the last cell of its file becomes `piece` ("Q", "R", "B" or "N").
+    pub fn make_move_with_promotion(&mut self, from: String, to: String, piece: String) -> Result<(), JsValue> {
+        self.parse_and_apply_move(from.as_str(), to.as_str(), piece.as_str())
+            .map_err(|error| JsValue::from_str(error.to_string().as_str()))
+    }
+
+    /// Validates and plays a move given in Standard Algebraic Notation, e.g. "Nf5", "exd6" or "c10=Q".
+    pub fn make_move_san(&mut self, san: String) -> Result<(), JsValue> {
+        self.apply_san(san.as_str())
+            .map_err(|error| JsValue::from_str(error.to_string().as_str()))
+    }
+
+    /// Returns every move played so far as a JSON array, oldest first.
+    /// 
+    /// Each entry has `from` and `to` as coordinates, `piece` and `captured` as piece IDs like "WP",
+    /// `promotion` as "Q", "R", "B" or "N", the `check` and `checkmate` flags, and `san` in Standard Algebraic Notation.
+    pub fn get_move_history(&self) -> JsValue {
+        let history: Vec<serde_json::Value> = self.move_history.iter().map(|record| {
+            serde_json::json!({
+                "from": self.format_coordinate(&record.undo.move_info.from),
+                "to": self.format_coordinate(&record.undo.move_info.to),
+                "piece": Self::get_piece_id(&record.undo.move_info.piece),
+                "captured": record.undo.captured.map(|captured| Self::get_piece_id(&captured.piece)),
+                "promotion": record.promotion.map(|piece_type| Self::get_piece_id(&ChessPiece {
+                    piece_type,
+                    color: PieceColor::None
+                })),
+                "check": record.check,
+                "checkmate": record.checkmate,
+                "san": record.san
+            })
+        }).collect();
+        JsValue::from_str(serde_json::to_string(&history).unwrap().as_str())
+    }
+
+    /// Returns the piece IDs captured by each side as JSON, e.g. {"W":["BP"],"B":[]}.
+    pub fn get_captured_pieces(&self) -> JsValue {
+        let mut captured: HashMap<String, Vec<String>> = HashMap::new();
+        captured.insert(String::from("W"), self.captured_pieces[0].iter().map(Self::get_piece_id).collect());
+        captured.insert(String::from("B"), self.captured_pieces[1].iter().map(Self::get_piece_id).collect());
+        JsValue::from_str(serde_json::to_string(&captured).unwrap().as_str())
+    }
+
+    /// Checks if the opponent of `mover` has been Checkmated or Stalemated by the last move.
+    fn update_result(&mut self, mover: PieceColor) {
+        let opponent = pieces::get_opponent_color(mover);
+        let opponent_moves = moves::get_all_legal_moves(&self.board, opponent, self.en_passant);
+        if !validation::has_legal_moves(&opponent_moves) {
+            if validation::is_check(&self.board, opponent) {
+                self.result = GameResult::Checkmate(mover);
+            } else {
+                self.result = GameResult::Stalemate(mover);
+            }
+        }
+    }
+
+    pub fn get_result(&self) -> String {
+        match self.result {
+            GameResult::Ongoing => String::from("Ongoing"),
+            GameResult::Checkmate(_) => String::from("Checkmate"),
+            GameResult::Stalemate(_) => String::from("Stalemate"),
+            GameResult::Draw(_) => String::from("Draw"),
+            GameResult::Resignation(_) => String::from("Resignation")
+        }
+    }
+
+    /// Returns why the game was drawn, e.g. "ThreefoldRepetition", or an empty string if it wasn't.
+    pub fn get_draw_reason(&self) -> String {
+        match self.result {
+            GameResult::Draw(DrawReason::ThreefoldRepetition) => String::from("ThreefoldRepetition"),
+            GameResult::Draw(DrawReason::FiftyMoveRule) => String::from("FiftyMoveRule"),
+            GameResult::Draw(DrawReason::InsufficientMaterial) => String::from("InsufficientMaterial"),
+            GameResult::Draw(DrawReason::Agreement) => String::from("Agreement"),
+            _ => String::from("")
+        }
+    }
+
+    /// When enabled, a threefold repetition or the fifty-move rule only ends the game once a player calls `claim_draw`.
+    pub fn set_claim_based_draws(&mut self, enabled: bool) {
+        self.claim_based_draws = enabled;
+    }
+
+    /// Ends the game in a Draw if the current position has occurred at least three times, or the fifty-move rule applies.
+    /// Returns whether the claim succeeded.
+    pub fn claim_draw(&mut self) -> bool {
+        if self.result != GameResult::Ongoing {
+            return false;
+        }
+        match self.get_drawing_rule() {
+            Some(reason) => {
+                self.result = GameResult::Draw(reason);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Returns the number of halfmoves since the last Pawn move or capture.
+    pub fn get_halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// Returns how many more moves (by both sides) can be played before the fifty-move rule draws the game.
+    pub fn get_moves_until_fifty_move_draw(&self) -> u32 {
+        game::FIFTY_MOVE_RULE_HALFMOVES.saturating_sub(self.halfmove_clock).div_ceil(2)
+    }
+
+    /// Returns "W" or "B" for the side that won, or an empty string while the game is Ongoing or drawn.
+    pub fn get_winner(&self) -> String {
+        match self.result {
+            GameResult::Checkmate(PieceColor::White) | GameResult::Resignation(PieceColor::White) => String::from("W"),
+            GameResult::Checkmate(PieceColor::Black) | GameResult::Resignation(PieceColor::Black) => String::from("B"),
+            _ => String::from("")
+        }
+    }
+
+    /// Sets the rule used by `get_score`, either "Glinski" or "McCooey". Returns false for unknown rules.
+    pub fn set_scoring_rule(&mut self, rule: String) -> bool {
+        match scoring::get_scoring_rule(rule.as_str()) {
+            Some(scoring_rule) => {
+                self.scoring_rule = scoring_rule;
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Returns the points of each side as JSON, e.g. {"W":0.75,"B":0.25}, or null while the game is Ongoing.
+    pub fn get_score(&self) -> JsValue {
+        match scoring::get_score(&self.result, self.scoring_rule) {
+            Some(score) => {
+                let mut points: HashMap<String, f32> = HashMap::new();
+                points.insert(String::from("W"), score.white);
+                points.insert(String::from("B"), score.black);
+                JsValue::from_str(serde_json::to_string(&points).unwrap().as_str())
+            },
+            None => JsValue::NULL
+        }
+    }
+
+    /// Takes back the last move. Returns false if there is no move to take back.
+    pub fn undo(&mut self) -> bool {
+        let record = match self.move_history.pop() {
+            Some(record) => record,
+            None => return false
+        };
+        let color = record.undo.move_info.piece.color;
+        moves::unmake_move(&mut self.board, &record.undo);
+        if record.undo.captured.is_some() {
+            self.captured_pieces[Self::get_capturer_index(color)].pop();
+        }
+        self.en_passant = record.previous_en_passant;
+        self.halfmove_clock = record.previous_halfmove_clock;
+        if color == PieceColor::Black {
+            self.fullmove_number -= 1;
+        }
+        self.current_turn = color;
+        self.position_history.pop();
+        self.result = GameResult::Ongoing;
+        self.redo_history.push(record);
+        true
+    }
+
+    /// Plays the last move taken back by `undo` again. Returns false if there is no move to redo.
+    pub fn redo(&mut self) -> bool {
+        let move_info = match self.redo_history.last() {
+            Some(record) => record.undo.move_info,
+            None => return false
         };
+        // The record stays on the redo stack unless the move is played again.
+        if self.play_move(move_info.from, move_info.to, move_info.promotion).is_err() {
+            return false;
+        }
+        self.redo_history.pop();
+        true
+    }
+
+    /// Sets a tag pair for the exported game record, e.g. "Event", "White" or "Black".
+    /// "Result" and "Variant" are always taken from the game itself.
+    pub fn set_tag(&mut self, name: String, value: String) {
+        match self.tags.iter_mut().find(|(tag, _)| *tag == name) {
+            Some(tag) => tag.1 = value,
+            None => self.tags.push((name, value))
+        }
+    }
+
+    /// Exports the game as a PGN-style record with tag pairs and the moves in Standard Algebraic Notation.
+    pub fn to_pgn(&self) -> String {
+        let result = pgn::get_result_token(&self.result, self.scoring_rule);
+        let variant = match self.scoring_rule {
+            ScoringRule::Glinski => "Glinski",
+            ScoringRule::McCooey => "McCooey"
+        };
+        let mut tags: Vec<(String, String)> = Vec::new();
+        for name in pgn::REQUIRED_TAGS {
+            let value = match name {
+                "Result" => result.to_string(),
+                "Variant" => variant.to_string(),
+                _ => self.get_tag(name).unwrap_or("?").to_string()
+            };
+            tags.push((name.to_string(), value));
+        }
+        for (name, value) in self.tags.iter() {
+            if !pgn::REQUIRED_TAGS.contains(&name.as_str()) && name != "SetUp" && name != "FEN" {
+                tags.push((name.clone(), value.clone()));
+            }
+        }
+        if self.start_position != position::get_default_position() {
+            tags.push((String::from("SetUp"), String::from("1")));
+            tags.push((String::from("FEN"), position::write_position(&self.start_position)));
+        }
+        let sans: Vec<String> = self.move_history.iter().map(|record| record.san.clone()).collect();
+        pgn::write_pgn(&tags, &sans, result)
+    }
+
+    /// Reads a PGN-style record and replays its moves. Fails with the line and move of the first token that can't be played.
+    pub fn from_pgn(text: String) -> Result<Game, JsValue> {
+        Game::read_pgn(text.as_str()).map_err(|error| JsValue::from_str(error.to_string().as_str()))
     }
 
     pub fn is_in_check(&self) -> bool {
@@ -149,3 +522,580 @@ impl Game {
     }
 }
 
+impl Game {
+    fn new_from_position(position: Position) -> Game {
+        let mut game = Game {
+            id: 0,
+            move_history: Vec::new(),
+            board: position.board,
+            current_turn: position.next_turn,
+            en_passant: position.en_passant,
+            halfmove_clock: position.halfmove_clock,
+            fullmove_number: position.fullmove_number,
+            start_position: position,
+            captured_pieces: [Vec::new(), Vec::new()],
+            redo_history: Vec::new(),
+            position_history: vec![zobrist::get_hash(&position.board, position.next_turn, position.en_passant)],
+            claim_based_draws: false,
+            use_notation: false,
+            result: GameResult::Ongoing,
+            scoring_rule: ScoringRule::Glinski,
+            time_control: None,
+            tags: Vec::new()
+        };
+        game.update_result(pieces::get_opponent_color(position.next_turn));
+        if game.result == GameResult::Ongoing && validation::is_insufficient_material(&game.board) {
+            game.result = GameResult::Draw(DrawReason::InsufficientMaterial);
+        }
+        game
+    }
+
+    fn get_position(&self) -> Position {
+        Position {
+            board: self.board,
+            next_turn: self.current_turn,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number
+        }
+    }
+
+    /// Searches the current position for the best move of the side to move, or `None` when the game is over.
+    fn find_best_move(&self, limit: search::SearchLimit) -> Option<Move> {
+        if self.result != GameResult::Ongoing {
+            return None;
+        }
+        let history = self.position_history.split_last().map_or(&[][..], |(_, earlier)| earlier);
+        search::search(&self.get_position(), history, limit, self.scoring_rule).best_move
+    }
+
+    fn new_from_config(config: &AppConfig) -> Result<Game, Box<dyn std::error::Error>> {
+        let mut start_position = position::get_default_position();
+        if config.board_from_file {
+            start_position.board = if config.board.is_empty() {
+                board::get_board_from_file(config.board_path.as_str())?
+            } else {
+                board::parse_board(config.board.as_str())?
+            };
+        }
+        if let Some(side) = config.get_starting_side() {
+            start_position.next_turn = side;
+        }
+
+        let mut game = Game::new_from_position(start_position);
+        if let Some(rule) = config.get_scoring_rule() {
+            game.scoring_rule = rule;
+        }
+        if let Some(time_control) = config.time_control {
+            game.time_control = Some(time_control);
+            game.set_tag(
+                String::from("TimeControl"),
+                format!("{}+{}", time_control.initial_seconds, time_control.increment_seconds)
+            );
+        }
+        Ok(game)
+    }
+
+    fn read_save(save: &str) -> Result<Game, Box<dyn std::error::Error>> {
+        let mut value: serde_json::Value = serde_json::from_str(save)?;
+        match value.get("version").and_then(|version| version.as_u64()) {
+            Some(version) if version == SAVE_FORMAT_VERSION as u64 => {}
+            Some(version) => return Err(format!("Unsupported save format version {}", version).into()),
+            None => return Err("The save has no format version".into())
+        }
+        let game: Game = serde_json::from_value(value["game"].take())?;
+        game.validate_state()?;
+        Ok(game)
+    }
+
+    /// Checks a deserialised game for cells off the board and histories that don't fit together, which would
+    /// otherwise only fail later, when a move is played or taken back.
+    fn validate_state(&self) -> Result<(), String> {
+        let check_cell = |coordinates: &(usize, usize), field: &str| {
+            if moves::is_coordinate_in_bounds(coordinates) {
+                Ok(())
+            } else {
+                Err(format!("{} {},{} is off the board", field, coordinates.0, coordinates.1))
+            }
+        };
+        for (name, board) in [("board", &self.board), ("start_position", &self.start_position.board)] {
+            for (x, row) in board.0.iter().enumerate() {
+                for (y, cell) in row.iter().enumerate() {
+                    let is_piece = cell.is_some_and(|piece| piece.color != PieceColor::None && piece.piece_type != PieceType::None);
+                    if is_piece && !moves::is_coordinate_in_bounds(&(x, y)) {
+                        return Err(format!("The {} has a piece on {},{}, which is off the board", name, x, y));
+                    }
+                }
+            }
+        }
+        for en_passant in [self.en_passant, self.start_position.en_passant].iter().flatten() {
+            check_cell(en_passant, "En passant cell")?;
+        }
+        for record in self.move_history.iter().chain(self.redo_history.iter()) {
+            check_cell(&record.undo.move_info.from, "Move from")?;
+            check_cell(&record.undo.move_info.to, "Move to")?;
+            if let Some(captured) = record.undo.captured {
+                check_cell(&(captured.x, captured.y), "Captured piece")?;
+            }
+            if let Some(en_passant) = record.previous_en_passant {
+                check_cell(&en_passant, "En passant cell")?;
+            }
+        }
+        if self.current_turn == PieceColor::None {
+            return Err(String::from("No side is to move"));
+        }
+        if self.position_history.len() != self.move_history.len() + 1 {
+            return Err(String::from("The position history doesn't match the move history"));
+        }
+        Ok(())
+    }
+
+    fn read_bytes(bytes: &[u8]) -> Result<Game, Box<dyn std::error::Error>> {
+        let (start_position, game_moves) = encoding::decode_game(bytes)?;
+        let mut game = Game::new_from_position(start_position);
+        for move_info in game_moves {
+            game.apply_move(move_info.from, move_info.to, move_info.promotion)?;
+        }
+        Ok(game)
+    }
+
+    fn parse_and_apply_move(&mut self, from: &str, to: &str, piece: &str) -> Result<(), MoveError> {
+        let from = validation::parse_coordinate(from)?;
+        let to = validation::parse_coordinate(to)?;
+        let promotion = Self::parse_promotion_piece(piece)?;
+        self.apply_move(from, to, promotion)
+    }
+
+    fn apply_san(&mut self, san: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if self.result != GameResult::Ongoing {
+            return Err(Box::new(MoveError::GameOver));
+        }
+        let move_info = san::parse_san(&self.board, self.current_turn, self.en_passant, san)?;
+        self.apply_move(move_info.from, move_info.to, move_info.promotion)?;
+        Ok(())
+    }
+
+    fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    fn read_pgn(text: &str) -> Result<Game, pgn::PgnError> {
+        let record = pgn::read_pgn(text)?;
+        let mut game = Game::default();
+        if let Some((_, fen)) = record.tags.iter().find(|(name, _)| name == "FEN") {
+            let start_position = position::read_position(fen.as_str()).map_err(|error| pgn::PgnError {
+                line: pgn::get_tag_line(text, "FEN"),
+                move_number: 0,
+                token: fen.clone(),
+                message: error.to_string()
+            })?;
+            game = Game::new_from_position(start_position);
+        }
+        for (name, value) in record.tags.iter() {
+            if name == "Variant" {
+                if let Some(rule) = scoring::get_scoring_rule(value.as_str()) {
+                    game.scoring_rule = rule;
+                }
+            }
+            if name != "Result" {
+                game.set_tag(name.clone(), value.clone());
+            }
+        }
+        for (index, (san, line)) in record.moves.iter().enumerate() {
+            game.apply_san(san.as_str()).map_err(|error| pgn::PgnError {
+                line: *line,
+                move_number: index / 2 + 1,
+                token: san.clone(),
+                message: error.to_string()
+            })?;
+        }
+
+        // The result token settles games the moves leave open, and has to agree with games they end.
+        let result_error = |line: usize, move_number: usize, token: &str| pgn::PgnError {
+            line,
+            move_number,
+            token: token.to_string(),
+            message: String::from("Result doesn't match the moves")
+        };
+        let move_number = record.moves.len().div_ceil(2).max(1);
+        if game.result == GameResult::Ongoing {
+            game.result = pgn::get_declared_result(record.result.as_str())
+                .ok_or_else(|| result_error(record.result_line, move_number, record.result.as_str()))?;
+        } else if pgn::get_result_token(&game.result, game.scoring_rule) != record.result {
+            return Err(result_error(record.result_line, move_number, record.result.as_str()));
+        }
+        if let Some((_, tag)) = record.tags.iter().find(|(name, _)| name == "Result") {
+            if *tag != record.result {
+                return Err(result_error(pgn::get_tag_line(text, "Result"), 0, tag.as_str()));
+            }
+        }
+        Ok(game)
+    }
+
+    fn is_threefold_repetition(&self) -> bool {
+        match self.position_history.last() {
+            Some(current) => self.position_history.iter().filter(|key| *key == current).count() >= 3,
+            None => false
+        }
+    }
+
+    /// Returns the rule that allows the current position to be drawn, if any.
+    fn get_drawing_rule(&self) -> Option<DrawReason> {
+        if self.is_threefold_repetition() {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= game::FIFTY_MOVE_RULE_HALFMOVES {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Index into `captured_pieces` for the pieces captured by `color`.
+    fn get_capturer_index(color: PieceColor) -> usize {
+        if color == PieceColor::White { 0 } else { 1 }
+    }
+
+    /// Plays a new move. Any moves taken back with `undo` can no longer be redone.
+    fn apply_move(&mut self, from: (usize, usize), to: (usize, usize), promotion: Option<PieceType>) -> Result<(), MoveError> {
+        self.play_move(from, to, promotion)?;
+        self.redo_history.clear();
+        Ok(())
+    }
+
+    /// Checks the move against the current player's legal moves, plays it, and passes the turn.
+    fn play_move(&mut self, from: (usize, usize), to: (usize, usize), promotion: Option<PieceType>) -> Result<(), MoveError> {
+        if self.result != GameResult::Ongoing {
+            return Err(MoveError::GameOver);
+        }
+        let moving_piece = match self.board.0[from.0][from.1] {
+            Some(piece) if piece.color != PieceColor::None && piece.piece_type != PieceType::None => piece,
+            _ => return Err(MoveError::NoPiece(from))
+        };
+        if moving_piece.color != self.current_turn {
+            return Err(MoveError::WrongSide(from));
+        }
+        let legal_moves = moves::get_all_legal_moves(&self.board, self.current_turn, self.en_passant);
+        if !legal_moves.get(&from).is_some_and(|to_list| to_list.contains(&to)) {
+            return Err(MoveError::IllegalMove(from, to));
+        }
+        if let Some(piece_type) = promotion {
+            if !moves::is_promotion_move(&self.board, &from, &to) {
+                return Err(MoveError::InvalidPromotion(san::get_piece_letter(piece_type).to_string()));
+            }
+        }
+
+        let move_info = Move {
+            piece: moving_piece,
+            from,
+            to,
+            promotion
+        };
+        let san = san::format_san(&self.board, &move_info, self.en_passant);
+        let previous_hash = self.position_history.last().copied()
+            .unwrap_or_else(|| zobrist::get_hash(&self.board, self.current_turn, self.en_passant));
+        let undo = moves::make_move(&mut self.board, &move_info);
+        let captured = undo.captured;
+        if let Some(captured_piece) = captured {
+            self.captured_pieces[Self::get_capturer_index(moving_piece.color)].push(captured_piece.piece);
+        }
+        let previous_en_passant = self.en_passant;
+        self.en_passant = moves::get_en_passant_target(&move_info);
+        let previous_halfmove_clock = self.halfmove_clock;
+        if moving_piece.piece_type == PieceType::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if moving_piece.color == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+        self.update_result(moving_piece.color);
+        self.current_turn = pieces::get_opponent_color(self.current_turn);
+        self.position_history.push(zobrist::update_hash(previous_hash, &undo, previous_en_passant, self.en_passant));
+        if self.result == GameResult::Ongoing && validation::is_insufficient_material(&self.board) {
+            self.result = GameResult::Draw(DrawReason::InsufficientMaterial);
+        }
+        if self.result == GameResult::Ongoing && !self.claim_based_draws {
+            if let Some(reason) = self.get_drawing_rule() {
+                self.result = GameResult::Draw(reason);
+            }
+        }
+
+        self.move_history.push(MoveRecord {
+            undo,
+            previous_en_passant,
+            previous_halfmove_clock,
+            promotion: moves::get_promotion(&move_info),
+            check: validation::is_check(&self.board, self.current_turn),
+            checkmate: matches!(self.result, GameResult::Checkmate(_)),
+            san
+        });
+        Ok(())
+    }
+}
+
+
+impl Default for Game {
+    /// The standard game from the Glinski starting position.
+    fn default() -> Game {
+        Game::new_from_position(position::get_default_position())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays `plies` moves from the standard position, picking a different legal move each time.
+    fn get_played_game(plies: usize) -> Game {
+        let mut game = Game::default();
+        for ply in 0..plies {
+            let legal_moves = encoding::get_sorted_legal_moves(&game.board, game.current_turn, game.en_passant);
+            let move_info = legal_moves[(ply * 7) % legal_moves.len()];
+            game.apply_move(move_info.from, move_info.to, move_info.promotion).unwrap();
+        }
+        game
+    }
+
+    /// Plays moves given as pairs of cells in Glinski notation, e.g. ("f5", "f6").
+    fn play_moves(game: &mut Game, cells: &[(&str, &str)]) {
+        for (from, to) in cells {
+            game.parse_and_apply_move(from, to, "").unwrap();
+        }
+    }
+
+    fn get_sans(game: &Game) -> Vec<String> {
+        game.move_history.iter().map(|record| record.san.clone()).collect()
+    }
+
+    fn get_pgn_error(text: &str) -> (usize, usize, String) {
+        let error = Game::read_pgn(text).err().unwrap();
+        (error.line, error.move_number, error.token)
+    }
+
+    #[test]
+    fn save_round_trip_restores_the_game() {
+        let mut game = get_played_game(40);
+        game.set_tag(String::from("Event"), String::from("Club championship"));
+        assert!(game.undo() && game.undo() && game.undo());
+        assert!(!game.captured_pieces[0].is_empty() && !game.captured_pieces[1].is_empty());
+
+        let save = game.save();
+        let mut loaded = Game::read_save(save.as_str()).unwrap();
+        assert_eq!(loaded.save(), save);
+        assert_eq!(loaded.redo_history.len(), 3);
+        assert!(loaded.redo() && game.redo());
+        assert_eq!(loaded.save(), game.save());
+    }
+
+    #[test]
+    fn load_rejects_bad_saves() {
+        let mut game = get_played_game(40);
+        game.undo();
+        let save: serde_json::Value = serde_json::from_str(game.save().as_str()).unwrap();
+        let corrupt = |path: &str, value: serde_json::Value| {
+            let mut save = save.clone();
+            *save.pointer_mut(path).unwrap() = value;
+            Game::read_save(save.to_string().as_str()).err().map(|error| error.to_string())
+        };
+
+        assert!(corrupt("/version", serde_json::json!(1)).is_some());
+        assert!(corrupt("/game/en_passant", serde_json::json!([20, 3])).is_some());
+        assert!(corrupt("/game/move_history/0/undo/move_info/to", serde_json::json!([0, 0])).is_some());
+        assert!(corrupt("/game/redo_history/0/previous_en_passant", serde_json::json!([11, 11])).is_some());
+        assert!(corrupt("/game/board/0/0", serde_json::json!({"piece_type": "Queen", "color": "White"})).is_some());
+        assert!(corrupt("/game/board/10", serde_json::json!([])).is_some());
+        assert!(corrupt("/game/current_turn", serde_json::json!("None")).is_some());
+        assert!(corrupt("/game/position_history", serde_json::json!([])).is_some());
+
+        let (index, _) = game.move_history.iter().enumerate().find(|(_, record)| record.undo.captured.is_some()).unwrap();
+        assert!(corrupt(format!("/game/move_history/{}/undo/captured/x", index).as_str(), serde_json::json!(40)).is_some());
+        assert!(corrupt("/game/id", serde_json::json!(7)).is_none());
+    }
+
+    #[test]
+    fn pgn_round_trip_restores_the_game() {
+        let mut game = get_played_game(21);
+        game.set_tag(String::from("Event"), String::from("Club championship"));
+        game.set_tag(String::from("Round"), String::from("3"));
+
+        let loaded = Game::read_pgn(game.to_pgn().as_str()).unwrap();
+        assert_eq!(loaded.move_history, game.move_history);
+        assert_eq!(loaded.board, game.board);
+        assert_eq!(loaded.current_turn, game.current_turn);
+        assert_eq!(loaded.result, GameResult::Ongoing);
+        assert_eq!(loaded.get_tag("Round"), Some("3"));
+
+        let mut start = position::read_position("1/3/5/k2q3/9/11/11/5R5/11/11/6K4 w - 0 1").unwrap();
+        start.fullmove_number = 12;
+        let mut game = Game::new_from_position(start);
+        game.scoring_rule = ScoringRule::McCooey;
+        game.apply_san("Rxf8+").unwrap();
+        let loaded = Game::read_pgn(game.to_pgn().as_str()).unwrap();
+        assert_eq!(loaded.start_position, start);
+        assert_eq!(loaded.board, game.board);
+        assert_eq!(loaded.scoring_rule, ScoringRule::McCooey);
+    }
+
+    #[test]
+    fn pgn_result_token_is_applied() {
+        let sans = get_sans(&get_played_game(4)).join(" ");
+        let read_result = |token: &str| Game::read_pgn(format!("[Result \"{}\"]\n\n{} {}", token, sans, token).as_str()).unwrap().result;
+        assert_eq!(read_result("*"), GameResult::Ongoing);
+        assert_eq!(read_result("1-0"), GameResult::Resignation(PieceColor::White));
+        assert_eq!(read_result("0-1"), GameResult::Resignation(PieceColor::Black));
+        assert_eq!(read_result("1/2-1/2"), GameResult::Draw(DrawReason::Agreement));
+
+        // A Checkmate on the board must be recorded as such.
+        let mate = "[FEN \"k/3/1R3/2PP3/1n3b3/4p2B1n1/4P3b2/P7P2/9P1/11/10K w - 0 1\"]\n\n1. Rf10#";
+        assert_eq!(Game::read_pgn(format!("{} 1-0", mate).as_str()).unwrap().result, GameResult::Checkmate(PieceColor::White));
+        assert_eq!(get_pgn_error(format!("{} 1/2-1/2", mate).as_str()), (3, 1, String::from("1/2-1/2")));
+    }
+
+    #[test]
+    fn threefold_repetition_draws_or_waits_for_a_claim() {
+        // Both sides move a Knight out and back, so the start position occurs again after every 4 halfmoves.
+        let shuffle = [("h1", "i3"), ("h9", "i6"), ("i3", "h1"), ("i6", "h9")];
+        let mut game = Game::default();
+        play_moves(&mut game, &shuffle);
+        play_moves(&mut game, &shuffle[..3]);
+        assert_eq!(game.result, GameResult::Ongoing);
+        play_moves(&mut game, &shuffle[3..]);
+        assert_eq!(game.result, GameResult::Draw(DrawReason::ThreefoldRepetition));
+
+        let mut game = Game::default();
+        game.set_claim_based_draws(true);
+        play_moves(&mut game, &shuffle);
+        assert!(!game.claim_draw());
+        play_moves(&mut game, &shuffle);
+        assert_eq!(game.result, GameResult::Ongoing);
+        assert!(game.claim_draw());
+        assert_eq!(game.result, GameResult::Draw(DrawReason::ThreefoldRepetition));
+        assert!(!game.claim_draw());
+
+        assert!(game.undo());
+        assert_eq!(game.result, GameResult::Ongoing);
+        assert!(!game.claim_draw());
+    }
+
+    #[test]
+    fn fifty_move_rule_counts_halfmoves() {
+        let mut start = position::get_default_position();
+        start.halfmove_clock = 97;
+        let mut game = Game::new_from_position(start);
+        assert_eq!(game.get_moves_until_fifty_move_draw(), 2);
+        play_moves(&mut game, &[("h1", "i3"), ("h9", "i6")]);
+        assert_eq!(game.halfmove_clock, 99);
+        assert_eq!(game.get_moves_until_fifty_move_draw(), 1);
+        assert_eq!(game.result, GameResult::Ongoing);
+        play_moves(&mut game, &[("i3", "h1")]);
+        assert_eq!(game.halfmove_clock, 100);
+        assert_eq!(game.result, GameResult::Draw(DrawReason::FiftyMoveRule));
+
+        assert!(game.undo());
+        play_moves(&mut game, &[("f5", "f6")]);
+        assert_eq!(game.halfmove_clock, 0);
+
+        let mut game = Game::new_from_position(position::read_position("1/3/5/k2q3/9/11/11/5R5/11/11/6K4 w - 40 21").unwrap());
+        play_moves(&mut game, &[("f4", "f8")]);
+        assert_eq!(game.halfmove_clock, 0);
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_game() {
+        // Black can take the White Pawn that just stepped over g5 en passant.
+        let mut game = Game::new_from_position(position::read_position(test_support::EN_PASSANT).unwrap());
+        let en_passant = game.en_passant.unwrap();
+        let legal_moves = moves::get_all_legal_moves(&game.board, PieceColor::Black, game.en_passant);
+        let (from, _) = legal_moves.iter().find(|(from, to_list)| {
+            to_list.contains(&en_passant) && game.board.0[from.0][from.1].unwrap().piece_type == PieceType::Pawn
+        }).unwrap();
+        let get_white_pawns = |board: &Board| pieces::get_all_pieces(board, PieceColor::White)
+            .iter()
+            .filter(|piece| piece.piece.piece_type == PieceType::Pawn)
+            .count();
+        let (board, halfmove_clock, fullmove_number) = (game.board, game.halfmove_clock, game.fullmove_number);
+
+        game.apply_move(*from, en_passant, None).unwrap();
+        let board_after = game.board;
+        assert_eq!(get_white_pawns(&game.board), get_white_pawns(&board) - 1);
+        assert_eq!(game.captured_pieces[1], vec![ChessPiece { piece_type: PieceType::Pawn, color: PieceColor::White }]);
+        assert_eq!((game.en_passant, game.halfmove_clock, game.fullmove_number), (None, 0, fullmove_number + 1));
+
+        assert!(game.undo());
+        assert_eq!(game.board, board);
+        assert_eq!((game.en_passant, game.halfmove_clock, game.fullmove_number), (Some(en_passant), halfmove_clock, fullmove_number));
+        assert!(game.captured_pieces[1].is_empty());
+        assert_eq!(game.current_turn, PieceColor::Black);
+        assert!(game.redo());
+        assert_eq!(game.board, board_after);
+        assert_eq!(game.captured_pieces[1].len(), 1);
+
+        // A new move after undo drops the moves that could have been redone.
+        assert!(game.undo());
+        let (other_from, other_to) = legal_moves.iter()
+            .find_map(|(other_from, to_list)| to_list.iter().find(|to| **to != en_passant).map(|to| (*other_from, *to)))
+            .unwrap();
+        game.apply_move(other_from, other_to, None).unwrap();
+        assert!(game.redo_history.is_empty());
+        assert!(!game.redo());
+
+        // Taking back a Checkmate reopens the game.
+        let mut game = Game::new_from_position(position::read_position("k/3/1R3/2PP3/1n3b3/4p2B1n1/4P3b2/P7P2/9P1/11/10K w - 0 1").unwrap());
+        play_moves(&mut game, &[("e9", "f10")]);
+        assert_eq!(game.result, GameResult::Checkmate(PieceColor::White));
+        assert!(game.undo());
+        assert_eq!(game.result, GameResult::Ongoing);
+    }
+
+    #[test]
+    fn detects_checkmate_and_stalemate() {
+        let mut game = Game::from_position(String::from("k/3/1R3/2PP3/1n3b3/4p2B1n1/4P3b2/P7P2/9P1/11/10K w - 0 1")).unwrap();
+        assert_eq!(game.result, GameResult::Ongoing);
+        play_moves(&mut game, &[("e9", "f10")]);
+        assert_eq!(game.result, GameResult::Checkmate(PieceColor::White));
+        assert_eq!(game.get_winner(), "W");
+
+        // The Queen takes the last free cells from the Black King on a6 without giving Check.
+        let mut game = Game::from_position(String::from("1/3/5/K6/9/k10/11/6Q4/11/11/11 w - 0 1")).unwrap();
+        play_moves(&mut game, &[("g4", "h3")]);
+        assert_eq!(game.result, GameResult::Stalemate(PieceColor::White));
+        assert_eq!(game.get_winner(), "");
+        assert_eq!(Game::from_position(game.to_position()).unwrap().result, GameResult::Stalemate(PieceColor::White));
+    }
+
+    #[test]
+    fn rejects_invalid_moves() {
+        let mut game = Game::default();
+        let coordinates = |notation: &str| Cell::from_notation(notation).unwrap().to_coordinates();
+        assert_eq!(game.parse_and_apply_move("z9", "f6", ""), Err(MoveError::BadCoordinate(String::from("z9"))));
+        assert_eq!(game.parse_and_apply_move("1,2,3", "f6", ""), Err(MoveError::BadCoordinate(String::from("1,2,3"))));
+        assert_eq!(game.parse_and_apply_move("0,0", "f6", ""), Err(MoveError::OffBoard((0, 0))));
+        assert_eq!(game.parse_and_apply_move("f6", "f7", ""), Err(MoveError::NoPiece(coordinates("f6"))));
+        assert_eq!(game.parse_and_apply_move("f7", "f6", ""), Err(MoveError::WrongSide(coordinates("f7"))));
+        assert_eq!(game.parse_and_apply_move("f5", "f8", ""), Err(MoveError::IllegalMove(coordinates("f5"), coordinates("f8"))));
+        assert_eq!(game.parse_and_apply_move("f5", "f6", "K"), Err(MoveError::InvalidPromotion(String::from("K"))));
+        assert_eq!(game.parse_and_apply_move("f5", "f6", "Q"), Err(MoveError::InvalidPromotion(String::from("Q"))));
+        assert!(game.move_history.is_empty());
+        assert_eq!(game.parse_and_apply_move("f5", "f6", ""), Ok(()));
+
+        game.result = GameResult::Resignation(PieceColor::Black);
+        assert_eq!(game.parse_and_apply_move("f7", "f6", ""), Err(MoveError::GameOver));
+    }
+
+    #[test]
+    fn pgn_errors_report_line_and_move() {
+        let sans = get_sans(&get_played_game(4));
+        let text = format!("[Event \"?\"]\n\n1. {} {}\n2. {} Kf6 *", sans[0], sans[1], sans[2]);
+        assert_eq!(get_pgn_error(text.as_str()), (4, 2, String::from("Kf6")));
+        let text = format!("[Event \"?\"]\n\n1. {} {}\n2. Qz9", sans[0], sans[1]);
+        assert_eq!(get_pgn_error(text.as_str()), (4, 2, String::from("Qz9")));
+
+        // Stalemate scores need a Stalemate on the board, and the tag has to match the move text.
+        let text = format!("[Variant \"McCooey\"]\n\n1. {} {} 3/4-1/4", sans[0], sans[1]);
+        assert_eq!(get_pgn_error(text.as_str()), (3, 1, String::from("3/4-1/4")));
+        let text = format!("[Event \"?\"]\n[Result \"1-0\"]\n\n1. {} {} *", sans[0], sans[1]);
+        assert_eq!(get_pgn_error(text.as_str()), (2, 0, String::from("1-0")));
+        assert_eq!(get_pgn_error("[FEN \"11/11 w - 0 1\"]\n\n*"), (1, 0, String::from("11/11 w - 0 1")));
+    }
+}
@@ -1,7 +1,9 @@
-use crate::pieces::{ChessPiece, PieceColor, PieceType};
+use crate::pieces::{ChessPiece, PieceColor, PieceType, Piece, get_all_pieces};
 use crate::board::Board;
+use crate::hex::{Hex, DIAGONAL_DIRECTIONS, KNIGHT_OFFSETS, ORTHOGONAL_DIRECTIONS};
 use crate::validation::is_check;
 use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 
 pub type MoveList = HashMap<(usize, usize), Vec<(usize, usize)>>;
 
@@ -14,23 +16,25 @@ pub enum MoveDirection {
     LeftDown,
     RightUp,
     RightDown,
-
-    // Diagonal Move Directions
-    DiagonalLeft,
-    DiagonalRight,
-    DiagonalLeftUp,
-    DiagonalLeftDown,
-    DiagonalRightUp,
-    DiagonalRightDown,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Move {
     pub piece: ChessPiece,
     pub from: (usize, usize),
-    pub to: (usize, usize)
+    pub to: (usize, usize),
+    pub promotion: Option<PieceType>
 }
 
+/// Cells the Pawns start on, White's first. A Pawn may only step two cells forward from its own side's start cells.
+pub const PAWN_START_CELLS: [[(usize, usize); 9]; 2] = [
+    [(6,9), (6,8), (6,7), (6,6), (6,5), (7,4), (8,3), (9,2), (10,1)],
+    [(0,9), (1,8), (2,7), (3,6), (4,5), (4,4), (4,3), (4,2), (4,1)]
+];
+
+/// Pieces a Pawn can promote to, in the order the choices of one promotion move are listed.
+pub const PROMOTION_PIECES: [PieceType; 4] = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+
 pub fn is_coordinate_in_bounds(coordinates: &(usize, usize)) -> bool {
     let (x, y) = *coordinates;
     if x + y < 5 || x + y > 15 || (x >= 11 || y >= 11) {
@@ -39,297 +43,160 @@ pub fn is_coordinate_in_bounds(coordinates: &(usize, usize)) -> bool {
     true
 }
 
-pub fn get_move_coordinate(direction: MoveDirection, coordinates: &(usize, usize)) -> (usize, usize) {
+/// Checks if the coordinate is the last cell of its file for the given color, where that color's Pawns promote.
+pub fn is_promotion_coordinate(color: PieceColor, coordinates: &(usize, usize)) -> bool {
     let (x, y) = *coordinates;
-    let fallback: (usize, usize) = (11, 11); // Fall back for negative values
-    match direction {
-        MoveDirection::Up => {
-            if x >= 1 { (x-1, y) } else { fallback }
-        },
-        MoveDirection::Down => (x+1, y),
-
-        MoveDirection::LeftUp => {
-            if x >= 1 { (x-1, y+1) } else { fallback }
-        },
-        MoveDirection::LeftDown => (x, y+1),
-        
-        MoveDirection::RightUp => {
-            if y >= 1 { (x, y-1) } else { fallback }
-        },
-        MoveDirection::RightDown => {
-            if y >= 1 { (x+1, y-1) } else { fallback }
-        },
-        
-        MoveDirection::DiagonalLeft => {
-            if x >= 1 { (x-1, y+2) } else { fallback }
-        },
-        MoveDirection::DiagonalRight => {
-            if y >= 2 { (x+1, y-2) } else { fallback }
-        },
-        
-        MoveDirection::DiagonalLeftUp => {
-            if x >= 2 { (x-2, y+1) } else { fallback }
-        },
-        MoveDirection::DiagonalLeftDown => (x+1, y+1),
-        
-        MoveDirection::DiagonalRightUp => {
-            if x >= 1 && y >= 1 { (x-1, y-1) } else { fallback }
-        },
-        MoveDirection::DiagonalRightDown => {
-            if y >= 1 { (x+2, y-1) } else { fallback }
-        }
+    match color {
+        PieceColor::White => x == 5usize.saturating_sub(y),
+        PieceColor::Black => x == 10.min(15usize.saturating_sub(y)),
+        PieceColor::None => false
     }
 }
 
-pub fn get_pawn_moves(board: &Board, current_coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
-    let mut legal_moves: Vec<(usize, usize)> = Vec::new();    
-    let mut forward: MoveDirection = MoveDirection::Up;
-    let mut up_left: MoveDirection = MoveDirection::LeftUp;
-    let mut up_right: MoveDirection = MoveDirection::RightUp;
-    let mut starting_positions: [(usize, usize); 9] = [
-        (6,9), (6,8), (6,7), (6,6), (6,5), (7,4), (8,3), (9,2), (10,1)
-    ];
-    if let Some(current_piece) = board.0[current_coordinates.0][current_coordinates.1] {
-        if current_piece.color == PieceColor::Black {
-            forward = MoveDirection::Down;
-            up_left = MoveDirection::RightDown;
-            up_right = MoveDirection::LeftDown;
-            starting_positions = [
-                (0,9), (1,8), (2,7), (3,6), (4,5), (4,4), (4,3), (4,2), (4,1)
-            ];
-        }
-        // Checking up_left
-        let mut next_coordinate = get_move_coordinate(up_left, current_coordinates);
-        if let Some(left_up_piece) = board.0[next_coordinate.0][next_coordinate.1] {
-            if (left_up_piece.color != PieceColor::None) && (left_up_piece.color != current_piece.color) {
-                legal_moves.push(next_coordinate);
-            }
-        }
-        // Check up_right
-        next_coordinate = get_move_coordinate(up_right, current_coordinates);
-        if let Some(right_up_piece) = board.0[next_coordinate.0][next_coordinate.1] {
-            if (right_up_piece.color != PieceColor::None) && (right_up_piece.color != current_piece.color) {
-                legal_moves.push(next_coordinate);
-            }
-        }
-        // Check forward
-        let forward_count: usize = {
-            if starting_positions.contains(current_coordinates) {
-                2
-            } else {
-                1
-            }
-        };
-        let mut coordinate = *current_coordinates;
-        for x in 1..=forward_count {
-            next_coordinate = get_move_coordinate(forward, &coordinate);
-            if let Some(next_piece) = board.0[next_coordinate.0][next_coordinate.1] {
-                if x == 1 && next_piece.color != PieceColor::None {
-                    break;
-                }
-                if next_piece.color == PieceColor::None {
-                    legal_moves.push(next_coordinate);
-                }
-            }
-            coordinate = next_coordinate;
+/// Checks if moving the piece at `from` to `to` is a Pawn move that needs a promotion choice.
+pub fn is_promotion_move(board: &Board, from: &(usize, usize), to: &(usize, usize)) -> bool {
+    if let Some(piece) = board.0[from.0][from.1] {
+        if piece.piece_type == PieceType::Pawn {
+            return is_promotion_coordinate(piece.color, to);
         }
     }
-    
-    legal_moves
+    false
 }
 
-pub fn get_knight_moves(board: &Board, current_coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
-    let mut legal_moves: Vec<(usize, usize)> = Vec::new();
-    let (x, y) = current_coordinates;
-    let conditions = [
-        { x >= &1 }, { x >= &2 }, { x >= &3 },
-        { y >= &1 }, { y >= &2 }, { y >= &3 }
-    ];
-    if conditions[2] {
-        legal_moves.push((x-3, y+1));
-        legal_moves.push((x-3, y+2));
-    }
-    if conditions[1] && conditions[3] {
-        legal_moves.push((x-2, y-1));
-    }
-    if conditions[0] && conditions[4] {
-        legal_moves.push((x-1, y-2));
-    }
-    if conditions[5] {
-        legal_moves.push((x+1, y-3));
-        legal_moves.push((x+2, y-3));
-    }
-    if conditions[4] {
-        legal_moves.push((x+3, y-2));
-    }
-    if conditions[3] {
-        legal_moves.push((x+3, y-1));
+/// Returns the axial offset of one step in the direction.
+pub fn get_direction_offset(direction: MoveDirection) -> Hex {
+    match direction {
+        MoveDirection::Up => ORTHOGONAL_DIRECTIONS[0],
+        MoveDirection::RightUp => ORTHOGONAL_DIRECTIONS[1],
+        MoveDirection::RightDown => ORTHOGONAL_DIRECTIONS[2],
+        MoveDirection::Down => ORTHOGONAL_DIRECTIONS[3],
+        MoveDirection::LeftDown => ORTHOGONAL_DIRECTIONS[4],
+        MoveDirection::LeftUp => ORTHOGONAL_DIRECTIONS[5]
     }
-    legal_moves.push((x+2, y+1));
-    legal_moves.push((x+1, y+2));
-    if conditions[0] {
-        legal_moves.push((x-1, y+3));
+}
+
+/// Returns the cell one step away in the direction, or `None` past the edge of the board.
+pub fn get_move_coordinate(direction: MoveDirection, coordinates: &(usize, usize)) -> Option<(usize, usize)> {
+    (Hex::from_coordinates(coordinates) + get_direction_offset(direction)).to_coordinates()
+}
+
+/// Checks if the cell is empty. Cells holding `None` count as empty.
+fn is_empty(board: &Board, coordinates: &(usize, usize)) -> bool {
+    match board.0[coordinates.0][coordinates.1] {
+        Some(piece) => piece.color == PieceColor::None,
+        None => true
     }
-    if conditions[1] {
-        legal_moves.push((x-2, y+3));
+}
+
+/// Checks if the cell holds a piece of the other side than `color`.
+fn is_opponent(board: &Board, coordinates: &(usize, usize), color: PieceColor) -> bool {
+    match board.0[coordinates.0][coordinates.1] {
+        Some(piece) => piece.color != PieceColor::None && piece.color != color,
+        None => false
     }
+}
 
-    // Only keeping coordinates that are inside the board bounds
-    legal_moves.retain(is_coordinate_in_bounds);
+/// Generates the Pawn's moves. `en_passant` is the cell skipped by an opponent Pawn's double step on the previous move, if any.
+pub fn get_pawn_moves(board: &Board, current_coordinates: &(usize, usize), en_passant: Option<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut legal_moves: Vec<(usize, usize)> = Vec::new();
+    let current_piece = match board.0[current_coordinates.0][current_coordinates.1] {
+        Some(piece) if piece.color != PieceColor::None => piece,
+        _ => return legal_moves
+    };
+    let (forward, up_left, up_right, starting_positions) = match current_piece.color {
+        PieceColor::Black => (MoveDirection::Down, MoveDirection::RightDown, MoveDirection::LeftDown, PAWN_START_CELLS[1]),
+        _ => (MoveDirection::Up, MoveDirection::LeftUp, MoveDirection::RightUp, PAWN_START_CELLS[0])
+    };
 
-    // Function to check if any coordinate has the same colored piece
-    fn is_not_overlapping(board: &Board, current_coordinates: &(usize, usize), next_coordinates: &(usize, usize)) -> bool {
-        if let Some(next_piece) = board.0[next_coordinates.0][next_coordinates.1] {
-            if let Some(current_piece) = board.0[current_coordinates.0][current_coordinates.1] {
-                if next_piece.color != PieceColor::None && next_piece.color == current_piece.color {
-                    return false;
-                }
+    // Captures, including en passant onto the cell skipped by the opponent's double step
+    for direction in [up_left, up_right] {
+        if let Some(next_coordinate) = get_move_coordinate(direction, current_coordinates) {
+            let is_en_passant = en_passant == Some(next_coordinate)
+                && is_en_passant_capture(board, current_coordinates, &next_coordinate);
+            if is_opponent(board, &next_coordinate, current_piece.color) || is_en_passant {
+                legal_moves.push(next_coordinate);
             }
         }
-        true
     }
 
-    let is_valid_closure = |item: &(usize, usize)| -> bool {
-        is_not_overlapping(board, current_coordinates, item)
-    };
+    // Forward, two cells from the starting positions
+    let forward_count: usize = if starting_positions.contains(current_coordinates) { 2 } else { 1 };
+    let ray = Hex::from_coordinates(current_coordinates).get_ray(get_direction_offset(forward));
+    for next_coordinate in ray.take(forward_count).filter_map(Hex::to_coordinates) {
+        if !is_empty(board, &next_coordinate) {
+            break;
+        }
+        legal_moves.push(next_coordinate);
+    }
 
-    legal_moves.retain(is_valid_closure);
-    
     legal_moves
 }
 
-pub fn get_bishop_moves(board: &Board, current_coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
+/// Generates the target cells the piece can step onto, skipping cells held by its own side.
+fn get_step_moves(board: &Board, current_coordinates: &(usize, usize), targets: impl Iterator<Item = Hex>) -> Vec<(usize, usize)> {
+    let color = match board.0[current_coordinates.0][current_coordinates.1] {
+        Some(piece) if piece.color != PieceColor::None => piece.color,
+        _ => return Vec::new()
+    };
+    targets.filter_map(Hex::to_coordinates)
+        .filter(|next_coordinate| is_empty(board, next_coordinate) || is_opponent(board, next_coordinate, color))
+        .collect()
+}
+
+/// Generates the cells a piece can slide to in the directions, stopping at the first piece and capturing it if it's an opponent.
+fn get_sliding_moves(board: &Board, current_coordinates: &(usize, usize), directions: &[Hex]) -> Vec<(usize, usize)> {
     let mut legal_moves: Vec<(usize, usize)> = Vec::new();
-    let mut stop: bool = false;
-    let mut coordinates = *current_coordinates;
-    let check_directions = [
-        MoveDirection::DiagonalLeftUp, MoveDirection::DiagonalLeft, MoveDirection::DiagonalLeftDown,
-        MoveDirection::DiagonalRightUp, MoveDirection::DiagonalRight, MoveDirection::DiagonalRightDown,
-    ];
-    for direction in check_directions.iter() {
-        while !stop {
-            let next_coordinate = get_move_coordinate(*direction, &coordinates);
-            if is_coordinate_in_bounds(&next_coordinate) {
-                if let Some(next_piece) = board.0[next_coordinate.0][next_coordinate.1] {
-                    if let Some(current_piece) = board.0[current_coordinates.0][current_coordinates.1] {
-                        if next_piece.color != PieceColor::None { 
-                            if next_piece.color != current_piece.color {
-                                legal_moves.push(next_coordinate);
-                                stop = true;
-                            } else {
-                                stop = true;
-                            }
-                        } else {
-                            legal_moves.push(next_coordinate);
-                            coordinates = next_coordinate;
-                        }
-                    }
-                } else {
-                    legal_moves.push(next_coordinate);
-                    coordinates = next_coordinate;
-                }
-            } else {
-                stop = true;
+    let color = match board.0[current_coordinates.0][current_coordinates.1] {
+        Some(piece) if piece.color != PieceColor::None => piece.color,
+        _ => return legal_moves
+    };
+    let current = Hex::from_coordinates(current_coordinates);
+    for direction in directions {
+        for next_coordinate in current.get_ray(*direction).filter_map(Hex::to_coordinates) {
+            if is_empty(board, &next_coordinate) {
+                legal_moves.push(next_coordinate);
+                continue;
+            }
+            if is_opponent(board, &next_coordinate, color) {
+                legal_moves.push(next_coordinate);
             }
+            break;
         }
-        coordinates = *current_coordinates;
-        stop = false;
     }
-    
     legal_moves
 }
 
+pub fn get_knight_moves(board: &Board, current_coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
+    let current = Hex::from_coordinates(current_coordinates);
+    get_step_moves(board, current_coordinates, KNIGHT_OFFSETS.into_iter().map(|offset| current + offset))
+}
+
+pub fn get_bishop_moves(board: &Board, current_coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
+    get_sliding_moves(board, current_coordinates, &DIAGONAL_DIRECTIONS)
+}
+
 pub fn get_rook_moves(board: &Board, current_coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
-    let mut legal_moves: Vec<(usize, usize)> = Vec::new();
-    let mut stop: bool = false;
-    let mut coordinates = *current_coordinates;
-    let check_directions = [
-        MoveDirection::LeftUp, MoveDirection::Up, MoveDirection::RightUp,
-        MoveDirection::LeftDown, MoveDirection::Down, MoveDirection::RightDown,
-    ];
-    for direction in check_directions.iter() {
-        while !stop {
-            let next_coordinate = get_move_coordinate(*direction, &coordinates);
-            if is_coordinate_in_bounds(&next_coordinate) {
-                if let Some(next_piece) = board.0[next_coordinate.0][next_coordinate.1] {
-                    if let Some(current_piece) = board.0[current_coordinates.0][current_coordinates.1] {
-                        if next_piece.color != PieceColor::None { 
-                            if next_piece.color != current_piece.color {
-                                legal_moves.push(next_coordinate);
-                                stop = true;
-                            } else {
-                                stop = true;
-                            }
-                        } else {
-                            legal_moves.push(next_coordinate);
-                            coordinates = next_coordinate;
-                        }
-                    }
-                } else {
-                    legal_moves.push(next_coordinate);
-                    coordinates = next_coordinate;
-                }
-            } else {
-                stop = true;
-            }
-        }
-        coordinates = *current_coordinates;
-        stop = false;
-    }
-    legal_moves
+    get_sliding_moves(board, current_coordinates, &ORTHOGONAL_DIRECTIONS)
 }
 
 pub fn get_queen_moves(board: &Board, current_coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
-    let mut legal_moves: Vec<(usize, usize)> = Vec::new();
-    let mut regular_moves: Vec<(usize, usize)> = get_rook_moves(board, current_coordinates);
-    let mut diagonal_moves: Vec<(usize, usize)> = get_bishop_moves(board, current_coordinates);
-    legal_moves.extend(regular_moves.drain(..));
-    legal_moves.extend(diagonal_moves.drain(..));
+    let mut legal_moves: Vec<(usize, usize)> = get_rook_moves(board, current_coordinates);
+    legal_moves.append(&mut get_bishop_moves(board, current_coordinates));
     legal_moves
 }
 
 pub fn get_king_moves(board: &Board, current_coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
-    let mut legal_moves = Vec::new();
-    let directions = [
-        MoveDirection::Up,
-        MoveDirection::DiagonalRightUp,
-        MoveDirection::RightUp,
-        MoveDirection::DiagonalRight,
-        MoveDirection::RightDown,
-        MoveDirection::DiagonalRightDown,
-        MoveDirection::Down,
-        MoveDirection::DiagonalLeftDown,
-        MoveDirection::LeftDown,
-        MoveDirection::DiagonalLeft,
-        MoveDirection::LeftUp,
-        MoveDirection::DiagonalLeftUp
-    ];
-    for direction in directions {
-        let next_coordinate: (usize, usize) = get_move_coordinate(direction, current_coordinates);
-        if is_coordinate_in_bounds(&next_coordinate) {
-            if let Some(current_piece) = board.0[current_coordinates.0][current_coordinates.1] {
-                if let Some(next_piece) = board.0[next_coordinate.0][next_coordinate.1] {
-                    if next_piece.color != PieceColor::None {
-                        if next_piece.color != current_piece.color {
-                            legal_moves.push(next_coordinate);
-                        }
-                    } else {
-                        legal_moves.push(next_coordinate);
-                    }
-                }
-            }
-        }
-    }
-    legal_moves
+    let current = Hex::from_coordinates(current_coordinates);
+    get_step_moves(board, current_coordinates, current.get_orthogonal_neighbours().chain(current.get_diagonal_neighbours()))
 }
 
-pub fn get_legal_moves(board: &Board, coordinates: &(usize, usize)) -> Vec<(usize, usize)> {
+pub fn get_legal_moves(board: &Board, coordinates: &(usize, usize), en_passant: Option<(usize, usize)>) -> Vec<(usize, usize)> {
     let mut legal_moves: Vec<(usize, usize)> = Vec::new();
     if let Some(piece) = board.0[coordinates.0][coordinates.1] { match piece.color {
         PieceColor::White => {
             match piece.piece_type {
                 PieceType::Pawn => {
-                    legal_moves = get_pawn_moves(&board, coordinates);
+                    legal_moves = get_pawn_moves(&board, coordinates, en_passant);
                 },
                 PieceType::Knight => {
                     legal_moves = get_knight_moves(&board, coordinates);
@@ -354,7 +221,7 @@ pub fn get_legal_moves(board: &Board, coordinates: &(usize, usize)) -> Vec<(usiz
         PieceColor::Black => {
             match piece.piece_type {
                 PieceType::Pawn => {
-                    legal_moves = get_pawn_moves(&board, coordinates);
+                    legal_moves = get_pawn_moves(&board, coordinates, en_passant);
                 },
                 PieceType::Knight => {
                     legal_moves = get_knight_moves(&board, coordinates);
@@ -383,17 +250,154 @@ pub fn get_legal_moves(board: &Board, coordinates: &(usize, usize)) -> Vec<(usiz
     legal_moves
 }
 
+/// Returns the cell of the Pawn that is removed when a Pawn of `color` captures en passant onto `target`.
+fn get_en_passant_victim(color: PieceColor, target: &(usize, usize)) -> Option<(usize, usize)> {
+    match color {
+        PieceColor::Black => get_move_coordinate(MoveDirection::Up, target),
+        _ => get_move_coordinate(MoveDirection::Down, target)
+    }
+}
+
+/// Checks if the Pawn on `from` moving onto the empty cell `to` of another file is an en passant capture.
+pub fn is_en_passant_capture(board: &Board, from: &(usize, usize), to: &(usize, usize)) -> bool {
+    if from.1 == to.1 || !is_coordinate_in_bounds(to) {
+        return false;
+    }
+    let pawn = match board.0[from.0][from.1] {
+        Some(piece) if piece.piece_type == PieceType::Pawn => piece,
+        _ => return false
+    };
+    if let Some(target_piece) = board.0[to.0][to.1] {
+        if target_piece.color != PieceColor::None {
+            return false;
+        }
+    }
+    let victim = match get_en_passant_victim(pawn.color, to) {
+        Some(victim) => victim,
+        None => return false
+    };
+    match board.0[victim.0][victim.1] {
+        Some(piece) => piece.piece_type == PieceType::Pawn && piece.color != PieceColor::None && piece.color != pawn.color,
+        None => false
+    }
+}
+
+/// Returns the cell skipped by a Pawn's double step, which the opponent may capture en passant on the next move.
+pub fn get_en_passant_target(move_info: &Move) -> Option<(usize, usize)> {
+    let (from, to) = (move_info.from, move_info.to);
+    let distance = Hex::from_coordinates(&from).distance(&Hex::from_coordinates(&to));
+    if move_info.piece.piece_type != PieceType::Pawn || from.1 != to.1 || distance != 2 {
+        return None;
+    }
+    Some(((from.0 + to.0) / 2, from.1))
+}
+
+/// Returns the piece type a Pawn becomes with this move, or `None` if the move isn't a promotion.
+pub fn get_promotion(move_info: &Move) -> Option<PieceType> {
+    if move_info.piece.piece_type == PieceType::Pawn && is_promotion_coordinate(move_info.piece.color, &move_info.to) {
+        return Some(move_info.promotion.unwrap_or(PieceType::Queen));
+    }
+    None
+}
+
 /// Function to actually change the place of a Piece on the Board.
-pub fn execute_move(board: &mut Board, move_info: &Move) {
+/// 
+/// A Pawn landing on the last cell of its file is replaced by `move_info.promotion`, or a Queen if no choice was made.
+/// Returns the captured piece along with the cell it was captured on, if any.
+pub fn execute_move(board: &mut Board, move_info: &Move) -> Option<Piece> {
     let to: (usize, usize) = move_info.to;
     let from: (usize, usize) = move_info.from;
 
-    board.0[to.0][to.1] = Some(move_info.piece);
+    let captured_at = match get_en_passant_victim(move_info.piece.color, &to) {
+        Some(victim) if is_en_passant_capture(board, &from, &to) => victim,
+        _ => to
+    };
+    let captured = match board.0[captured_at.0][captured_at.1] {
+        Some(piece) if piece.color != PieceColor::None && piece.piece_type != PieceType::None => {
+            Some(Piece { x: captured_at.0, y: captured_at.1, piece })
+        },
+        _ => None
+    };
+    board.0[captured_at.0][captured_at.1] = Some(ChessPiece {
+        piece_type: PieceType::None,
+        color: PieceColor::None
+    });
+
+    let mut piece = move_info.piece;
+    if let Some(promotion) = get_promotion(move_info) {
+        piece.piece_type = promotion;
+    }
+    board.0[to.0][to.1] = Some(piece);
     
     board.0[from.0][from.1] = Some(ChessPiece {
         piece_type: PieceType::None,
         color: PieceColor::None
     });
+
+    captured
+}
+
+/// What `unmake_move` needs to take back a move made by `make_move`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UndoRecord {
+    pub move_info: Move,
+    /// The captured piece and its cell, which differs from `move_info.to` for en passant.
+    pub captured: Option<Piece>,
+    /// The origin and target cells as they were, as either may hold `None` or an empty piece.
+    previous_cells: [Option<ChessPiece>; 2]
+}
+
+/// Plays the move on the board in place and returns what's needed to take it back with `unmake_move`.
+pub fn make_move(board: &mut Board, move_info: &Move) -> UndoRecord {
+    let (from, to) = (move_info.from, move_info.to);
+    let previous_cells = [board.0[from.0][from.1], board.0[to.0][to.1]];
+    let captured = execute_move(board, move_info);
+    UndoRecord { move_info: *move_info, captured, previous_cells }
+}
+
+/// Restores the board to how it was before `make_move` returned the record.
+pub fn unmake_move(board: &mut Board, undo: &UndoRecord) {
+    let (from, to) = (undo.move_info.from, undo.move_info.to);
+    if let Some(captured) = undo.captured {
+        board.0[captured.x][captured.y] = Some(captured.piece);
+    }
+    board.0[to.0][to.1] = undo.previous_cells[1];
+    board.0[from.0][from.1] = undo.previous_cells[0];
+}
+
+/// Checks if a Pawn of `by_color` could capture on the cell.
+pub fn is_attacked_by_pawn(board: &Board, cell: Hex, by_color: PieceColor) -> bool {
+    // A Pawn attacks the cell from where a Pawn of the other side on the cell would capture.
+    let pawn_directions = match by_color {
+        PieceColor::White => [MoveDirection::RightDown, MoveDirection::LeftDown],
+        PieceColor::Black => [MoveDirection::LeftUp, MoveDirection::RightUp],
+        PieceColor::None => return false
+    };
+    pawn_directions.iter().any(|direction| {
+        (cell + get_direction_offset(*direction)).to_coordinates()
+            .and_then(|(x, y)| board.0[x][y])
+            .is_some_and(|piece| piece.color == by_color && piece.piece_type == PieceType::Pawn)
+    })
+}
+
+/// Checks if a piece of `by_color` could capture on the cell, looking outwards from it instead of generating moves.
+pub fn is_square_attacked(board: &Board, cell: &(usize, usize), by_color: PieceColor) -> bool {
+    let target = Hex::from_coordinates(cell);
+    let holds = |hex: Hex, piece_types: &[PieceType]| -> bool {
+        match hex.to_coordinates().and_then(|(x, y)| board.0[x][y]) {
+            Some(piece) => piece.color == by_color && piece_types.contains(&piece.piece_type),
+            None => false
+        }
+    };
+    let get_first_piece = |direction: &Hex| -> Option<Hex> {
+        target.get_ray(*direction).find(|hex| hex.to_coordinates().is_some_and(|next| !is_empty(board, &next)))
+    };
+
+    is_attacked_by_pawn(board, target, by_color)
+        || KNIGHT_OFFSETS.iter().any(|offset| holds(target + *offset, &[PieceType::Knight]))
+        || target.get_orthogonal_neighbours().chain(target.get_diagonal_neighbours()).any(|hex| holds(hex, &[PieceType::King]))
+        || ORTHOGONAL_DIRECTIONS.iter().filter_map(get_first_piece).any(|hex| holds(hex, &[PieceType::Rook, PieceType::Queen]))
+        || DIAGONAL_DIRECTIONS.iter().filter_map(get_first_piece).any(|hex| holds(hex, &[PieceType::Bishop, PieceType::Queen]))
 }
 
 /// Removes the moves that Keep King in Check or lead King to Check
@@ -406,21 +410,126 @@ pub fn remove_check_moves(board: &Board, move_list: &mut MoveList) {
             }
         }
     }
-    for (from, moves) in move_list.clone().iter() {
-        let mut filtered_moves: Vec<(usize, usize)> = Vec::new();
-        for to in moves.iter() {
-            let mut board_copy = board.clone();
-            let move_info = Move {
-                piece: board_copy.0[from.0][from.1].unwrap(),
-                from: *from,
-                to: *to
-            };
-            execute_move(&mut board_copy, &move_info);
-            if !is_check(&board_copy, king_color) {
-                filtered_moves.push(*to);
+    let mut board_copy = *board;
+    for (from, moves) in move_list.iter_mut() {
+        let Some(piece) = board.0[from.0][from.1] else { continue };
+        moves.retain(|to| {
+            let undo = make_move(&mut board_copy, &Move { piece, from: *from, to: *to, promotion: None });
+            let is_legal = !is_check(&board_copy, king_color);
+            unmake_move(&mut board_copy, &undo);
+            is_legal
+        });
+    }
+    move_list.retain(|_, moves| !moves.is_empty());
+}
+
+/// Generates every legal move for the pieces of `color`, with the moves that leave the King in Check removed.
+pub fn get_all_legal_moves(board: &Board, color: PieceColor, en_passant: Option<(usize, usize)>) -> MoveList {
+    let mut move_list: MoveList = MoveList::new();
+    for piece in get_all_pieces(board, color) {
+        let piece_moves = get_legal_moves(board, &(piece.x, piece.y), en_passant);
+        move_list.insert((piece.x, piece.y), piece_moves);
+    }
+    remove_check_moves(board, &mut move_list);
+    move_list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboard::{self, Bitboard};
+    use crate::cell::Cell;
+    use crate::position::{self, read_position};
+    use crate::test_support::{EN_PASSANT, POSITIONS};
+
+    fn get_coordinates(notation: &str) -> (usize, usize) {
+        Cell::from_notation(notation).unwrap().to_coordinates()
+    }
+
+    fn get_move(board: &Board, from: &str, to: &str) -> Move {
+        let from = get_coordinates(from);
+        Move { piece: board.0[from.0][from.1].unwrap(), from, to: get_coordinates(to), promotion: None }
+    }
+
+    #[test]
+    fn only_double_steps_set_the_en_passant_cell() {
+        let board = position::get_default_position().board;
+        assert_eq!(get_en_passant_target(&get_move(&board, "e4", "e6")), Some(get_coordinates("e5")));
+        assert_eq!(get_en_passant_target(&get_move(&board, "e4", "e5")), None);
+        assert_eq!(get_en_passant_target(&get_move(&board, "f7", "f5")), Some(get_coordinates("f6")));
+        assert_eq!(get_en_passant_target(&get_move(&board, "d1", "f4")), None);
+    }
+
+    #[test]
+    fn en_passant_removes_the_pawn_that_double_stepped() {
+        // White's g4 Pawn has just stepped over g5 to g6.
+        let position = read_position(EN_PASSANT).unwrap();
+        let (target, victim) = (get_coordinates("g5"), get_coordinates("g6"));
+        assert_eq!(position.en_passant, Some(target));
+        let (from, _) = get_all_legal_moves(&position.board, PieceColor::Black, position.en_passant)
+            .into_iter()
+            .find(|(from, to_list)| to_list.contains(&target) && position.board.0[from.0][from.1].unwrap().piece_type == PieceType::Pawn)
+            .unwrap();
+        assert!(is_en_passant_capture(&position.board, &from, &target));
+
+        let mut board = position.board;
+        let pawn = board.0[from.0][from.1].unwrap();
+        let captured = execute_move(&mut board, &Move { piece: pawn, from, to: target, promotion: None });
+        let white_pawn = ChessPiece { piece_type: PieceType::Pawn, color: PieceColor::White };
+        assert_eq!(captured, Some(Piece { x: victim.0, y: victim.1, piece: white_pawn }));
+        assert_eq!(board.0[victim.0][victim.1].unwrap().piece_type, PieceType::None);
+        assert_eq!(board.0[target.0][target.1], Some(pawn));
+        assert_eq!(get_en_passant_target(&Move { piece: pawn, from, to: target, promotion: None }), None);
+    }
+
+    #[test]
+    fn pawns_promote_on_the_last_cell_of_every_file() {
+        let file_lengths = [('a', 6), ('b', 7), ('c', 8), ('d', 9), ('e', 10), ('f', 11), ('g', 10), ('h', 9), ('i', 8), ('k', 7), ('l', 6)];
+        let pawn = |color: PieceColor| ChessPiece { piece_type: PieceType::Pawn, color };
+        for (file, length) in file_lengths {
+            let cell = |rank: usize| get_coordinates(format!("{}{}", file, rank).as_str());
+            assert!(Cell::from_notation(format!("{}{}", file, length + 1).as_str()).is_none(), "{}", file);
+            assert!(is_promotion_coordinate(PieceColor::White, &cell(length)), "{}", file);
+            assert!(!is_promotion_coordinate(PieceColor::White, &cell(length - 1)), "{}", file);
+            assert!(is_promotion_coordinate(PieceColor::Black, &cell(1)), "{}", file);
+            assert!(!is_promotion_coordinate(PieceColor::Black, &cell(2)), "{}", file);
+
+            let move_info = Move { piece: pawn(PieceColor::White), from: cell(length - 1), to: cell(length), promotion: None };
+            assert_eq!(get_promotion(&move_info), Some(PieceType::Queen));
+            assert_eq!(get_promotion(&Move { promotion: Some(PieceType::Knight), ..move_info }), Some(PieceType::Knight));
+            assert_eq!(get_promotion(&Move { piece: pawn(PieceColor::Black), ..move_info }), None);
+        }
+    }
+
+    #[test]
+    fn unmake_move_restores_the_board() {
+        for text in POSITIONS {
+            let position = read_position(text).unwrap();
+            let mut board = position.board;
+            for (from, to_list) in get_all_legal_moves(&board, position.next_turn, position.en_passant) {
+                for to in to_list {
+                    let move_info = Move { piece: board.0[from.0][from.1].unwrap(), from, to, promotion: None };
+                    let undo = make_move(&mut board, &move_info);
+                    assert_ne!(board, position.board);
+                    unmake_move(&mut board, &undo);
+                    assert_eq!(board, position.board);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn finds_attacked_cells() {
+        let start = position::write_position(&position::get_default_position());
+        for text in POSITIONS.iter().copied().chain([start.as_str()]) {
+            let board = read_position(text).unwrap().board;
+            let bitboard = Bitboard::from_board(&board);
+            for index in 0..bitboard::CELL_COUNT {
+                let cell = bitboard::get_cell_coordinates(index);
+                for color in [PieceColor::White, PieceColor::Black] {
+                    assert_eq!(is_square_attacked(&board, &cell, color), bitboard.is_attacked(index, color), "{:?} in {}", cell, text);
+                }
             }
         }
-        move_list.insert(*from, filtered_moves);
     }
-    move_list.retain(|_, moves| moves.len() > 0);
 }
@@ -0,0 +1,264 @@
+use crate::game::{DrawReason, GameResult};
+use crate::pieces::PieceColor;
+use crate::scoring::ScoringRule;
+use std::fmt;
+
+/// Tag pairs that always come first in an exported game, in this order.
+pub const REQUIRED_TAGS: [&str; 5] = ["Event", "White", "Black", "Result", "Variant"];
+
+/// Result tokens that end the move text, including the Glinski stalemate scores.
+const RESULT_TOKENS: [&str; 6] = ["1-0", "0-1", "1/2-1/2", "3/4-1/4", "1/4-3/4", "*"];
+
+/// Where and why a game record couldn't be read.
+/// 
+/// `line` is 1-based, and `move_number` is the full move number the token belongs to (0 for errors in the tag section).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnError {
+    pub line: usize,
+    pub move_number: usize,
+    pub token: String,
+    pub message: String
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.move_number == 0 {
+            write!(f, "Line {}: \"{}\": {}", self.line, self.token, self.message)
+        } else {
+            write!(f, "Line {}, move {}: \"{}\": {}", self.line, self.move_number, self.token, self.message)
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+/// A game record split into its tag pairs, its moves in SAN and the result token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnRecord {
+    pub tags: Vec<(String, String)>,
+    /// Each move along with the line it was found on.
+    pub moves: Vec<(String, usize)>,
+    pub result: String,
+    /// The line of the result token, or the last line if the record has none.
+    pub result_line: usize
+}
+
+/// Returns the result token for the Game, e.g. "1-0", or "3/4-1/4" for White giving stalemate under Glinski scoring.
+pub fn get_result_token(result: &GameResult, rule: ScoringRule) -> &'static str {
+    match result {
+        GameResult::Ongoing => "*",
+        GameResult::Checkmate(PieceColor::White) | GameResult::Resignation(PieceColor::White) => "1-0",
+        GameResult::Checkmate(_) | GameResult::Resignation(_) => "0-1",
+        GameResult::Stalemate(color) => match (rule, color) {
+            (ScoringRule::Glinski, PieceColor::White) => "3/4-1/4",
+            (ScoringRule::Glinski, PieceColor::Black) => "1/4-3/4",
+            _ => "1/2-1/2"
+        },
+        GameResult::Draw(_) => "1/2-1/2"
+    }
+}
+
+/// Returns the result a token declares for a game whose moves don't end it, i.e. a resignation or an agreed Draw.
+///
+/// Stalemate scores like "3/4-1/4" can only come from a Stalemate on the board, so they give `None` here.
+pub fn get_declared_result(token: &str) -> Option<GameResult> {
+    match token {
+        "*" => Some(GameResult::Ongoing),
+        "1-0" => Some(GameResult::Resignation(PieceColor::White)),
+        "0-1" => Some(GameResult::Resignation(PieceColor::Black)),
+        "1/2-1/2" => Some(GameResult::Draw(DrawReason::Agreement)),
+        _ => None
+    }
+}
+
+/// Returns the line of the tag pair with the name, or 0 if there is none.
+pub fn get_tag_line(text: &str, name: &str) -> usize {
+    let prefix = format!("[{}", name);
+    text.lines()
+        .position(|line| line.trim_start().strip_prefix(prefix.as_str()).is_some_and(|rest| rest.starts_with(char::is_whitespace)))
+        .map_or(0, |index| index + 1)
+}
+
+/// Writes a game record: the tag pairs, one per line, followed by the numbered moves and the result token.
+pub fn write_pgn(tags: &[(String, String)], moves: &[String], result: &str) -> String {
+    let mut pgn = String::new();
+    for (name, value) in tags {
+        let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+        pgn.push_str(format!("[{} \"{}\"]\n", name, escaped).as_str());
+    }
+    pgn.push('\n');
+
+    let mut tokens: Vec<String> = Vec::new();
+    for (index, san) in moves.iter().enumerate() {
+        if index % 2 == 0 {
+            tokens.push(format!("{}.", index / 2 + 1));
+        }
+        tokens.push(san.clone());
+    }
+    tokens.push(result.to_string());
+
+    // Keep lines of move text under 80 characters.
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length > 0 && line_length + token.len() + 1 > 79 {
+            pgn.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            pgn.push(' ');
+            line_length += 1;
+        }
+        line_length += token.len();
+        pgn.push_str(token.as_str());
+    }
+    pgn.push('\n');
+    pgn
+}
+
+fn parse_tag(line: &str, line_number: usize) -> Result<(String, String), PgnError> {
+    let error = |message: &str| PgnError {
+        line: line_number,
+        move_number: 0,
+        token: line.to_string(),
+        message: message.to_string()
+    };
+    let inner = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')).ok_or_else(|| error("Tag pair must be enclosed in [ ]"))?;
+    let (name, value) = inner.trim().split_once(char::is_whitespace).ok_or_else(|| error("Tag pair needs a name and a value"))?;
+    let value = value.trim().strip_prefix('"').and_then(|rest| rest.strip_suffix('"')).ok_or_else(|| error("Tag value must be quoted"))?;
+    Ok((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+/// Splits a game record into tag pairs and SAN moves, skipping move numbers, comments and annotations.
+/// 
+/// Comments can be `{ ... }`, which may span lines, or start with `;` and run to the end of the line.
+pub fn read_pgn(text: &str) -> Result<PgnRecord, PgnError> {
+    let mut record = PgnRecord {
+        tags: Vec::new(),
+        moves: Vec::new(),
+        result: String::from("*"),
+        result_line: 0
+    };
+    let mut in_comment = false;
+    let mut in_move_text = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+        if !in_comment && !in_move_text && trimmed.starts_with('[') {
+            record.tags.push(parse_tag(trimmed, line_number)?);
+            continue;
+        }
+
+        let mut rest = line;
+        while !rest.is_empty() {
+            if in_comment {
+                match rest.find('}') {
+                    Some(end) => {
+                        in_comment = false;
+                        rest = &rest[end + 1..];
+                        continue;
+                    },
+                    None => break
+                }
+            }
+            rest = rest.trim_start();
+            if rest.is_empty() || rest.starts_with(';') {
+                break;
+            }
+            if let Some(comment) = rest.strip_prefix('{') {
+                in_comment = true;
+                rest = comment;
+                continue;
+            }
+            let end = rest.find(|c: char| c.is_whitespace() || c == '{' || c == ';').unwrap_or(rest.len());
+            let token = &rest[..end];
+            rest = &rest[end..];
+            in_move_text = true;
+
+            if record.result_line != 0 {
+                return Err(PgnError {
+                    line: line_number,
+                    move_number: record.moves.len() / 2 + 1,
+                    token: token.to_string(),
+                    message: String::from("Unexpected token after the result")
+                });
+            }
+            if RESULT_TOKENS.contains(&token) {
+                record.result = token.to_string();
+                record.result_line = line_number;
+                continue;
+            }
+            if token.starts_with('$') {
+                continue;
+            }
+            // Move numbers like "12." or "12..." may be glued to the move that follows them.
+            let san = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+            let move_number = &token[..token.len() - san.len()];
+            if !move_number.is_empty() && !move_number.contains('.') {
+                return Err(PgnError {
+                    line: line_number,
+                    move_number: record.moves.len() / 2 + 1,
+                    token: token.to_string(),
+                    message: String::from("Unexpected token")
+                });
+            }
+            if !san.is_empty() {
+                record.moves.push((san.to_string(), line_number));
+            }
+        }
+    }
+
+    if in_comment {
+        return Err(PgnError {
+            line: text.lines().count(),
+            move_number: record.moves.len() / 2 + 1,
+            token: String::from("{"),
+            message: String::from("Comment is never closed")
+        });
+    }
+    if record.result_line == 0 {
+        record.result_line = text.lines().count();
+    }
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_error(text: &str) -> (usize, usize, String) {
+        let error = read_pgn(text).unwrap_err();
+        (error.line, error.move_number, error.token)
+    }
+
+    #[test]
+    fn reads_what_it_writes() {
+        let tags = vec![(String::from("Event"), String::from("Club \"Open\" \\ 2026"))];
+        let moves: Vec<String> = ["f6", "e6", "Nd3", "Qxb4+"].iter().map(|san| san.to_string()).collect();
+        let text = write_pgn(&tags, &moves, "1/2-1/2");
+
+        let record = read_pgn(text.as_str()).unwrap();
+        assert_eq!(record.tags, tags);
+        assert_eq!(record.moves.iter().map(|(san, _)| san.clone()).collect::<Vec<String>>(), moves);
+        assert_eq!(record.result, "1/2-1/2");
+        assert_eq!(record.result_line, 3);
+    }
+
+    #[test]
+    fn skips_comments_and_annotations() {
+        let text = "[Event \"?\"]\n\n1. f6 {a comment\nover two lines} e6 $1 ; to the end\n2.Nd3 *\n";
+        let record = read_pgn(text).unwrap();
+        let lines: Vec<usize> = record.moves.iter().map(|(_, line)| *line).collect();
+        assert_eq!(lines, vec![3, 4, 5]);
+        assert_eq!(record.result_line, 5);
+    }
+
+    #[test]
+    fn reports_where_records_are_malformed() {
+        assert_eq!(get_error("[Event \"?\"]\n[White Somebody]\n\n1. f6 *"), (2, 0, String::from("[White Somebody]")));
+        assert_eq!(get_error("[Event \"?\"\n\n1. f6 *"), (1, 0, String::from("[Event \"?\"")));
+        assert_eq!(get_error("[Event]\n\n1. f6 *"), (1, 0, String::from("[Event]")));
+        assert_eq!(get_error("\n1. f6 e6\n2. Nd3 2-0"), (3, 2, String::from("2-0")));
+        assert_eq!(get_error("1. f6 e6 2. Nd3 * Nd7"), (1, 2, String::from("Nd7")));
+        assert_eq!(get_error("1. f6 { never closed\n*"), (2, 1, String::from("{")));
+    }
+}
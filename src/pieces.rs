@@ -1,6 +1,7 @@
 use crate::board::Board;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -11,26 +12,35 @@ pub enum PieceType {
     None,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PieceColor {
     White,
     Black,
     None,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ChessPiece {
     pub piece_type: PieceType,
     pub color: PieceColor,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Piece {
     pub x: usize,
     pub y: usize,
     pub piece: ChessPiece
 }
 
+/// Returns the color of the other side. `PieceColor::None` has no opponent.
+pub fn get_opponent_color(color: PieceColor) -> PieceColor {
+    match color {
+        PieceColor::White => PieceColor::Black,
+        PieceColor::Black => PieceColor::White,
+        PieceColor::None => PieceColor::None
+    }
+}
+
 pub fn get_all_pieces(board: &Board, check_color: PieceColor) -> Vec<Piece> {
     let mut all_pieces: Vec<Piece> = Vec::new();
     for i in 0usize..11usize {
@@ -0,0 +1,258 @@
+use crate::board::{self, Board};
+use crate::cell::Cell;
+use crate::moves::{self, Move};
+use crate::pieces::{self, ChessPiece, PieceColor, PieceType};
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Everything needed to continue a Game from a position, like FEN in orthodox chess.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub board: Board,
+    pub next_turn: PieceColor,
+    pub en_passant: Option<(usize, usize)>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32
+}
+
+/// Reasons a position string can't be read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PositionError {
+    /// The string doesn't have the five space separated fields.
+    WrongFieldCount(usize),
+    /// There aren't exactly 11 ranks separated by "/".
+    WrongRankCount(usize),
+    /// The rank (1 to 11) has more or fewer cells than the board has on it.
+    WrongRankLength(usize),
+    /// The character isn't a piece letter or a digit.
+    UnknownPiece(char),
+    /// The side to move isn't "w" or "b".
+    BadSideToMove(String),
+    /// The en passant field isn't "-" or a cell like "e6".
+    BadEnPassant(String),
+    /// The halfmove clock or fullmove number isn't a number.
+    BadCounter(String)
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::WrongFieldCount(count) => write!(f, "Expected 5 fields but found {}", count),
+            PositionError::WrongRankCount(count) => write!(f, "Expected 11 ranks but found {}", count),
+            PositionError::WrongRankLength(rank) => write!(f, "Rank {} has the wrong number of cells", rank),
+            PositionError::UnknownPiece(piece) => write!(f, "Unknown piece \"{}\"", piece),
+            PositionError::BadSideToMove(side) => write!(f, "Side to move must be \"w\" or \"b\", found \"{}\"", side),
+            PositionError::BadEnPassant(cell) => write!(f, "Bad en passant cell \"{}\"", cell),
+            PositionError::BadCounter(counter) => write!(f, "Bad move counter \"{}\"", counter)
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Returns the position at the start of a Game.
+pub fn get_default_position() -> Position {
+    Position {
+        board: board::get_default_board(),
+        next_turn: PieceColor::White,
+        en_passant: None,
+        halfmove_clock: 0,
+        fullmove_number: 1
+    }
+}
+
+/// Returns the cells of a rank from the "a" file to the "l" file.
+fn get_rank_cells(rank: usize) -> Vec<Cell> {
+    "abcdefghikl".chars()
+        .filter_map(|file| Cell::from_notation(format!("{}{}", file, rank).as_str()))
+        .collect()
+}
+
+fn get_piece_letter(piece: &ChessPiece) -> Option<char> {
+    let letter = match piece.piece_type {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+        PieceType::None => return None
+    };
+    match piece.color {
+        PieceColor::White => Some(letter.to_ascii_uppercase()),
+        PieceColor::Black => Some(letter),
+        PieceColor::None => None
+    }
+}
+
+fn decode_piece_letter(letter: char) -> Option<ChessPiece> {
+    let piece_type = match letter.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None
+    };
+    let color = if letter.is_ascii_uppercase() { PieceColor::White } else { PieceColor::Black };
+    Some(ChessPiece { piece_type, color })
+}
+
+/// Writes the position on a single line.
+/// 
+/// The ranks go from 11 down to 1, separated by "/", each listing its cells from the "a" file to the "l" file.
+/// White pieces are upper case, Black pieces lower case, and runs of empty cells are written as a number.
+/// Then follow the side to move, the en passant cell or "-", the halfmove clock and the fullmove number.
+/// The starting position is `b/qbk/n1b1n/r5r/ppppppppp/11/5P5/4P1P4/3P1B1P3/2P2B2P2/1PRNQBKNRP1 w - 0 1`.
+/// Plays a move on the position, keeping only what the next move's legal moves depend on. The counters aren't updated.
+pub fn play_move(position: &mut Position, move_info: &Move) {
+    moves::execute_move(&mut position.board, move_info);
+    position.en_passant = moves::get_en_passant_target(move_info);
+    position.next_turn = pieces::get_opponent_color(position.next_turn);
+}
+
+pub fn write_position(position: &Position) -> String {
+    let mut ranks: Vec<String> = Vec::new();
+    for rank in (1..=11).rev() {
+        let mut rank_string = String::new();
+        let mut empty_cells = 0;
+        for cell in get_rank_cells(rank) {
+            let letter = position.board.0[cell.x][cell.y].and_then(|piece| get_piece_letter(&piece));
+            match letter {
+                Some(letter) => {
+                    if empty_cells > 0 {
+                        rank_string.push_str(empty_cells.to_string().as_str());
+                        empty_cells = 0;
+                    }
+                    rank_string.push(letter);
+                },
+                None => empty_cells += 1
+            }
+        }
+        if empty_cells > 0 {
+            rank_string.push_str(empty_cells.to_string().as_str());
+        }
+        ranks.push(rank_string);
+    }
+    let next_turn = if position.next_turn == PieceColor::Black { "b" } else { "w" };
+    let en_passant = position.en_passant
+        .and_then(|coordinates| Cell::from_coordinates(&coordinates))
+        .map(|cell| cell.to_notation())
+        .unwrap_or_else(|| String::from("-"));
+    format!("{} {} {} {} {}", ranks.join("/"), next_turn, en_passant, position.halfmove_clock, position.fullmove_number)
+}
+
+/// Reads a position written by `write_position`.
+pub fn read_position(text: &str) -> Result<Position, PositionError> {
+    let fields: Vec<&str> = text.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(PositionError::WrongFieldCount(fields.len()));
+    }
+
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+    if ranks.len() != 11 {
+        return Err(PositionError::WrongRankCount(ranks.len()));
+    }
+    let mut board = board::new();
+    for (index, rank_string) in ranks.iter().enumerate() {
+        let rank = 11 - index;
+        let cells = get_rank_cells(rank);
+        let mut cell_index = 0;
+        let mut chars = rank_string.chars().peekable();
+        while let Some(c) = chars.next() {
+            if let Some(digit) = c.to_digit(10) {
+                let mut empty_cells = digit as usize;
+                while let Some(next_digit) = chars.peek().and_then(|next| next.to_digit(10)) {
+                    empty_cells = empty_cells * 10 + next_digit as usize;
+                    chars.next();
+                }
+                cell_index += empty_cells;
+            } else {
+                let piece = decode_piece_letter(c).ok_or(PositionError::UnknownPiece(c))?;
+                let cell = cells.get(cell_index).ok_or(PositionError::WrongRankLength(rank))?;
+                board.0[cell.x][cell.y] = Some(piece);
+                cell_index += 1;
+            }
+        }
+        if cell_index != cells.len() {
+            return Err(PositionError::WrongRankLength(rank));
+        }
+    }
+
+    let next_turn = match fields[1] {
+        "w" => PieceColor::White,
+        "b" => PieceColor::Black,
+        side => return Err(PositionError::BadSideToMove(side.to_string()))
+    };
+    let en_passant = match fields[2] {
+        "-" => None,
+        cell => Some(Cell::from_notation(cell).ok_or_else(|| PositionError::BadEnPassant(cell.to_string()))?.to_coordinates())
+    };
+    let halfmove_clock: u32 = fields[3].parse().map_err(|_| PositionError::BadCounter(fields[3].to_string()))?;
+    let fullmove_number: u32 = fields[4].parse().map_err(|_| PositionError::BadCounter(fields[4].to_string()))?;
+
+    Ok(Position {
+        board,
+        next_turn,
+        en_passant,
+        halfmove_clock,
+        fullmove_number
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::{self, Move};
+
+    const DEFAULT_POSITION: &str = "b/qbk/n1b1n/r5r/ppppppppp/11/5P5/4P1P4/3P1B1P3/2P2B2P2/1PRNQBKNRP1 w - 0 1";
+
+    #[test]
+    fn writes_default_board() {
+        assert_eq!(write_position(&get_default_position()), DEFAULT_POSITION);
+    }
+
+    #[test]
+    fn reads_default_board() {
+        assert_eq!(read_position(DEFAULT_POSITION), Ok(get_default_position()));
+    }
+
+    #[test]
+    fn round_trips_en_passant_and_counters() {
+        let mut position = get_default_position();
+        let move_info = Move {
+            piece: position.board.0[6][5].unwrap(),
+            from: (6, 5),
+            to: (4, 5),
+            promotion: None
+        };
+        moves::execute_move(&mut position.board, &move_info);
+        position.next_turn = PieceColor::Black;
+        position.en_passant = moves::get_en_passant_target(&move_info);
+        position.halfmove_clock = 7;
+        position.fullmove_number = 12;
+
+        let text = write_position(&position);
+        assert!(text.ends_with(" b f6 7 12"));
+        assert_eq!(read_position(text.as_str()), Ok(position));
+    }
+
+    #[test]
+    fn rejects_malformed_positions() {
+        assert_eq!(read_position("b/qbk w - 0 1"), Err(PositionError::WrongRankCount(2)));
+        assert_eq!(
+            read_position("b/qbk/n1b1n/r5r/ppppppppp/12/5P5/4P1P4/3P1B1P3/2P2B2P2/1PRNQBKNRP1 w - 0 1"),
+            Err(PositionError::WrongRankLength(6))
+        );
+        assert_eq!(
+            read_position("x/qbk/n1b1n/r5r/ppppppppp/11/5P5/4P1P4/3P1B1P3/2P2B2P2/1PRNQBKNRP1 w - 0 1"),
+            Err(PositionError::UnknownPiece('x'))
+        );
+        assert_eq!(
+            read_position("b/qbk/n1b1n/r5r/ppppppppp/11/5P5/4P1P4/3P1B1P3/2P2B2P2/1PRNQBKNRP1 x - 0 1"),
+            Err(PositionError::BadSideToMove(String::from("x")))
+        );
+        assert_eq!(read_position("b/qbk"), Err(PositionError::WrongFieldCount(1)));
+    }
+}
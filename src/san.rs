@@ -0,0 +1,273 @@
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::moves::{self, Move};
+use crate::pieces::{ChessPiece, PieceColor, PieceType, get_opponent_color};
+use crate::validation::is_check;
+use std::fmt;
+
+/// Reasons a move in Standard Algebraic Notation can't be resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SanError {
+    /// The text isn't a move in SAN, e.g. "Nz5".
+    Malformed(String),
+    /// No legal move of the side to play matches the text.
+    Illegal(String),
+    /// More than one legal move matches the text, e.g. "Nf5" when both Knights can go to f5.
+    Ambiguous(String)
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::Malformed(san) => write!(f, "\"{}\" is not a move in algebraic notation", san),
+            SanError::Illegal(san) => write!(f, "\"{}\" is not a legal move", san),
+            SanError::Ambiguous(san) => write!(f, "\"{}\" matches more than one legal move", san)
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+pub fn get_piece_letter(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::King => "K",
+        PieceType::Queen => "Q",
+        PieceType::Rook => "R",
+        PieceType::Bishop => "B",
+        PieceType::Knight => "N",
+        PieceType::Pawn | PieceType::None => ""
+    }
+}
+
+fn get_piece_type(letter: char) -> Option<PieceType> {
+    match letter {
+        'K' => Some(PieceType::King),
+        'Q' => Some(PieceType::Queen),
+        'R' => Some(PieceType::Rook),
+        'B' => Some(PieceType::Bishop),
+        'N' => Some(PieceType::Knight),
+        _ => None
+    }
+}
+
+fn is_capture(board: &Board, move_info: &Move) -> bool {
+    if let Some(target) = board.0[move_info.to.0][move_info.to.1] {
+        if target.color != PieceColor::None && target.piece_type != PieceType::None {
+            return true;
+        }
+    }
+    moves::is_en_passant_capture(board, &move_info.from, &move_info.to)
+}
+
+/// Formats a legal move in Standard Algebraic Notation, e.g. "Nf5", "exd6", "Qxb4+" or "c10=Q#".
+/// 
+/// `board` and `en_passant` describe the position before the move. When several pieces of the same type can reach
+/// the target cell, the origin's file, rank or both are added to tell them apart.
+pub fn format_san(board: &Board, move_info: &Move, en_passant: Option<(usize, usize)>) -> String {
+    let (from, to) = match (Cell::from_coordinates(&move_info.from), Cell::from_coordinates(&move_info.to)) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return String::new()
+    };
+    let capture = is_capture(board, move_info);
+    let mut san = String::new();
+
+    if move_info.piece.piece_type == PieceType::Pawn {
+        if capture {
+            san.push(from.get_file());
+        }
+    } else {
+        san.push_str(get_piece_letter(move_info.piece.piece_type));
+        let legal_moves = moves::get_all_legal_moves(board, move_info.piece.color, en_passant);
+        let mut rivals: Vec<Cell> = Vec::new();
+        for (other_from, to_list) in legal_moves.iter() {
+            if *other_from != move_info.from && to_list.contains(&move_info.to) {
+                if let Some(other_piece) = board.0[other_from.0][other_from.1] {
+                    if other_piece.piece_type == move_info.piece.piece_type {
+                        rivals.extend(Cell::from_coordinates(other_from));
+                    }
+                }
+            }
+        }
+        if !rivals.is_empty() {
+            if rivals.iter().all(|rival| rival.get_file() != from.get_file()) {
+                san.push(from.get_file());
+            } else if rivals.iter().all(|rival| rival.get_rank() != from.get_rank()) {
+                san.push_str(from.get_rank().to_string().as_str());
+            } else {
+                san.push_str(from.to_notation().as_str());
+            }
+        }
+    }
+    if capture {
+        san.push('x');
+    }
+    san.push_str(to.to_notation().as_str());
+    if let Some(promotion) = moves::get_promotion(move_info) {
+        san.push('=');
+        san.push_str(get_piece_letter(promotion));
+    }
+
+    let mut board_after = *board;
+    moves::execute_move(&mut board_after, move_info);
+    let opponent = get_opponent_color(move_info.piece.color);
+    if is_check(&board_after, opponent) {
+        let en_passant_after = moves::get_en_passant_target(move_info);
+        if moves::get_all_legal_moves(&board_after, opponent, en_passant_after).is_empty() {
+            san.push('#');
+        } else {
+            san.push('+');
+        }
+    }
+    san
+}
+
+/// Resolves a move in Standard Algebraic Notation to one of `color`'s legal moves.
+/// 
+/// Check and annotation suffixes ("+", "#", "!", "?") are ignored, and a missing "x" on captures is accepted.
+/// A Pawn promotion without "=Q" style suffix promotes to a Queen.
+pub fn parse_san(board: &Board, color: PieceColor, en_passant: Option<(usize, usize)>, san: &str) -> Result<Move, SanError> {
+    let malformed = || SanError::Malformed(san.to_string());
+    let mut text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+    let mut promotion: Option<PieceType> = None;
+    if let Some((rest, piece)) = text.split_once('=') {
+        let mut chars = piece.chars();
+        promotion = match (chars.next().and_then(get_piece_type), chars.next()) {
+            (Some(PieceType::King), _) | (None, _) | (_, Some(_)) => return Err(malformed()),
+            (piece_type, None) => piece_type
+        };
+        text = rest;
+    }
+
+    let piece_type = match text.chars().next().and_then(get_piece_type) {
+        Some(piece_type) => {
+            text = &text[1..];
+            piece_type
+        },
+        None => PieceType::Pawn
+    };
+
+    // The target cell is the last file letter together with the digits after it.
+    let rank_start = text.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if rank_start == 0 || rank_start == text.len() {
+        return Err(malformed());
+    }
+    let target_start = text[..rank_start].char_indices().last().map(|(index, _)| index).ok_or_else(malformed)?;
+    let to = Cell::from_notation(&text[target_start..]).ok_or_else(malformed)?;
+    text = &text[..target_start];
+
+    let capture = text.ends_with('x');
+    text = text.trim_end_matches('x');
+
+    let mut from_file: Option<char> = None;
+    let mut from_rank: Option<usize> = None;
+    let mut chars = text.chars().peekable();
+    if let Some(c) = chars.peek().copied() {
+        if c.is_ascii_lowercase() {
+            from_file = Some(c);
+            chars.next();
+        }
+    }
+    let rank_text: String = chars.collect();
+    if !rank_text.is_empty() {
+        from_rank = Some(rank_text.parse().map_err(|_| malformed())?);
+    }
+
+    let legal_moves = moves::get_all_legal_moves(board, color, en_passant);
+    let mut candidates: Vec<Move> = Vec::new();
+    for (from, to_list) in legal_moves.iter() {
+        let piece: ChessPiece = match board.0[from.0][from.1] {
+            Some(piece) if piece.piece_type == piece_type => piece,
+            _ => continue
+        };
+        let from_cell = match Cell::from_coordinates(from) {
+            Some(cell) => cell,
+            None => continue
+        };
+        if from_file.is_some_and(|file| file != from_cell.get_file()) || from_rank.is_some_and(|rank| rank != from_cell.get_rank()) {
+            continue;
+        }
+        if to_list.contains(&to.to_coordinates()) {
+            candidates.push(Move {
+                piece,
+                from: *from,
+                to: to.to_coordinates(),
+                promotion
+            });
+        }
+    }
+
+    match candidates.len() {
+        0 => Err(SanError::Illegal(san.to_string())),
+        1 => {
+            let move_info = candidates[0];
+            if capture && !is_capture(board, &move_info) {
+                return Err(SanError::Illegal(san.to_string()));
+            }
+            if promotion.is_some() && !moves::is_promotion_move(board, &move_info.from, &move_info.to) {
+                return Err(SanError::Illegal(san.to_string()));
+            }
+            Ok(move_info)
+        },
+        _ => Err(SanError::Ambiguous(san.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::read_position;
+    use crate::test_support::get_board;
+
+    fn get_move(board: &Board, from: &str, to: &str, promotion: Option<PieceType>) -> Move {
+        let from = Cell::from_notation(from).unwrap().to_coordinates();
+        Move { piece: board.0[from.0][from.1].unwrap(), from, to: Cell::from_notation(to).unwrap().to_coordinates(), promotion }
+    }
+
+    /// Checks that the move formats as `expected` and that the text resolves back to the same move.
+    fn assert_san(board: &Board, move_info: Move, expected: &str) {
+        assert_eq!(format_san(board, &move_info, None), expected);
+        assert_eq!(parse_san(board, move_info.piece.color, None, expected), Ok(move_info));
+    }
+
+    #[test]
+    fn disambiguates_by_file_rank_or_cell() {
+        let board = get_board(&[("d8", PieceColor::White, PieceType::Knight), ("e8", PieceColor::White, PieceType::Knight)]);
+        assert_san(&board, get_move(&board, "d8", "f11", None), "Ndf11");
+        assert_eq!(parse_san(&board, PieceColor::White, None, "Nf11"), Err(SanError::Ambiguous(String::from("Nf11"))));
+
+        let board = get_board(&[("g10", PieceColor::White, PieceType::Knight), ("g9", PieceColor::White, PieceType::Knight)]);
+        assert_san(&board, get_move(&board, "g10", "d9", None), "N10d9");
+
+        let board = get_board(&[
+            ("c8", PieceColor::White, PieceType::Knight),
+            ("c7", PieceColor::White, PieceType::Knight),
+            ("i8", PieceColor::White, PieceType::Knight)
+        ]);
+        assert_san(&board, get_move(&board, "c8", "f9", None), "Nc8f9");
+    }
+
+    #[test]
+    fn formats_captures_checks_and_promotions() {
+        let position = read_position("1/3/5/k2q3/9/11/11/5R5/11/11/6K4 w - 0 1").unwrap();
+        assert_san(&position.board, get_move(&position.board, "f4", "f8", None), "Rxf8+");
+
+        let position = read_position("k/3/1R3/2PP3/1n3b3/4p2B1n1/4P3b2/P7P2/9P1/11/10K w - 0 1").unwrap();
+        assert_san(&position.board, get_move(&position.board, "e9", "f10", None), "Rf10#");
+
+        let board = get_board(&[("c7", PieceColor::White, PieceType::Pawn)]);
+        assert_san(&board, get_move(&board, "c7", "c8", Some(PieceType::Knight)), "c8=N");
+        assert_eq!(parse_san(&board, PieceColor::White, None, "c8").unwrap().promotion, None);
+    }
+
+    #[test]
+    fn rejects_bad_moves() {
+        let board = get_board(&[("c7", PieceColor::White, PieceType::Pawn), ("f6", PieceColor::White, PieceType::Knight)]);
+        for text in ["", "Nz5", "N", "c8=K", "c8=QQ", "Nf6x"] {
+            assert_eq!(parse_san(&board, PieceColor::White, None, text), Err(SanError::Malformed(text.to_string())), "{}", text);
+        }
+        for text in ["Qf6", "Nf7", "c6", "Nxd9", "e3=Q"] {
+            assert_eq!(parse_san(&board, PieceColor::White, None, text), Err(SanError::Illegal(text.to_string())), "{}", text);
+        }
+    }
+}
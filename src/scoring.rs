@@ -0,0 +1,81 @@
+use crate::game::GameResult;
+use crate::pieces::PieceColor;
+use serde::{Deserialize, Serialize};
+
+/// Rule sets for turning a finished Game into points for each side.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ScoringRule {
+    /// Stalemate gives 3/4 of a point to the side that stalemated and 1/4 to the stalemated side.
+    Glinski,
+    /// Stalemate is scored like any other draw, 1/2 of a point each.
+    McCooey
+}
+
+/// Points earned by each side from a finished Game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Score {
+    pub white: f32,
+    pub black: f32
+}
+
+pub fn get_scoring_rule(name: &str) -> Option<ScoringRule> {
+    match name.to_lowercase().as_str() {
+        "glinski" => Some(ScoringRule::Glinski),
+        "mccooey" => Some(ScoringRule::McCooey),
+        _ => None
+    }
+}
+
+/// Splits a point between the sides, `winner_points` going to `winner`. With no winner it is split evenly.
+fn split_points(winner: PieceColor, winner_points: f32) -> Score {
+    match winner {
+        PieceColor::White => Score { white: winner_points, black: 1.0 - winner_points },
+        PieceColor::Black => Score { white: 1.0 - winner_points, black: winner_points },
+        PieceColor::None => Score { white: 0.5, black: 0.5 }
+    }
+}
+
+/// Returns the points for each side under `rule`, or `None` while the Game is still Ongoing.
+pub fn get_score(result: &GameResult, rule: ScoringRule) -> Option<Score> {
+    match result {
+        GameResult::Ongoing => None,
+        GameResult::Checkmate(winner) | GameResult::Resignation(winner) => Some(split_points(*winner, 1.0)),
+        GameResult::Stalemate(stalemating_side) => match rule {
+            ScoringRule::Glinski => Some(split_points(*stalemating_side, 0.75)),
+            ScoringRule::McCooey => Some(split_points(PieceColor::None, 0.5))
+        },
+        GameResult::Draw(_) => Some(split_points(PieceColor::None, 0.5))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::DrawReason;
+
+    #[test]
+    fn scores_results_under_both_rules() {
+        let score = |white: f32, black: f32| Some(Score { white, black });
+        let expected = [
+            (GameResult::Ongoing, None, None),
+            (GameResult::Checkmate(PieceColor::White), score(1.0, 0.0), score(1.0, 0.0)),
+            (GameResult::Checkmate(PieceColor::Black), score(0.0, 1.0), score(0.0, 1.0)),
+            (GameResult::Resignation(PieceColor::Black), score(0.0, 1.0), score(0.0, 1.0)),
+            (GameResult::Stalemate(PieceColor::White), score(0.75, 0.25), score(0.5, 0.5)),
+            (GameResult::Stalemate(PieceColor::Black), score(0.25, 0.75), score(0.5, 0.5)),
+            (GameResult::Draw(DrawReason::ThreefoldRepetition), score(0.5, 0.5), score(0.5, 0.5)),
+            (GameResult::Draw(DrawReason::InsufficientMaterial), score(0.5, 0.5), score(0.5, 0.5))
+        ];
+        for (result, glinski, mccooey) in expected {
+            assert_eq!(get_score(&result, ScoringRule::Glinski), glinski, "{:?}", result);
+            assert_eq!(get_score(&result, ScoringRule::McCooey), mccooey, "{:?}", result);
+        }
+    }
+
+    #[test]
+    fn reads_rule_names() {
+        assert_eq!(get_scoring_rule("Glinski"), Some(ScoringRule::Glinski));
+        assert_eq!(get_scoring_rule("mccooey"), Some(ScoringRule::McCooey));
+        assert_eq!(get_scoring_rule("Shafran"), None);
+    }
+}
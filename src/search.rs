@@ -0,0 +1,363 @@
+use crate::bitboard;
+use crate::board::Board;
+use crate::encoding;
+use crate::eval;
+use crate::moves::{self, Move, UndoRecord};
+use crate::pieces::{self, PieceColor, PieceType};
+use crate::position::Position;
+use crate::scoring::ScoringRule;
+use crate::validation;
+use crate::zobrist;
+
+/// Deepest search `Game::best_move` accepts as a depth. Larger values are time limits in milliseconds.
+pub const MAX_DEPTH: u32 = 32;
+
+/// Score of giving Checkmate right now. Mates further away score a little less, so the quickest one is preferred.
+pub const MATE_SCORE: i32 = 1_000_000;
+
+const INFINITY: i32 = 2 * MATE_SCORE;
+
+/// Quiescence search stops extending captures past this many plies from the root.
+const MAX_PLY: usize = 64;
+
+/// How often, in nodes, the clock is read during a timed search.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// When to stop searching.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchLimit {
+    /// Search exactly this many moves ahead, plus captures.
+    Depth(u32),
+    /// Deepen until the time in milliseconds runs out, keeping the result of the last finished depth.
+    Time(u32)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchResult {
+    /// `None` if the side to move has no legal moves.
+    pub best_move: Option<Move>,
+    /// In centipawns from the point of view of the side to move.
+    pub score: i32,
+    /// The deepest search that finished.
+    pub depth: u32,
+    pub nodes: u64
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = Date)]
+    fn now() -> f64;
+}
+
+/// Milliseconds from a fixed point, used to measure the time a search takes.
+#[cfg(target_arch = "wasm32")]
+fn get_time_ms() -> f64 {
+    now()
+}
+
+/// Milliseconds from a fixed point, used to measure the time a search takes.
+#[cfg(not(target_arch = "wasm32"))]
+fn get_time_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Returns what being Stalemated is worth to the stalemated side under `rule`.
+///
+/// Glinski scoring gives it only 1/4 of a point, so it ranks between a draw and a loss, above any material.
+fn get_stalemate_score(rule: ScoringRule) -> i32 {
+    match rule {
+        ScoringRule::Glinski => -MATE_SCORE / 2,
+        ScoringRule::McCooey => 0
+    }
+}
+
+/// What `Searcher::unmake` needs besides the board's undo record.
+struct SearchUndo {
+    undo: UndoRecord,
+    en_passant: Option<(usize, usize)>,
+    halfmove_clock: u32
+}
+
+/// The state of one search: the position it walks through and what it has learned about move ordering.
+struct Searcher {
+    board: Board,
+    next_turn: PieceColor,
+    en_passant: Option<(usize, usize)>,
+    halfmove_clock: u32,
+    /// Hashes of the game so far followed by the positions on the current search path.
+    hashes: Vec<u64>,
+    /// Score of the side to move when it is Stalemated.
+    stalemate_score: i32,
+    /// Two quiet moves per ply that recently caused a cutoff.
+    killers: Vec<[Option<Move>; 2]>,
+    /// How often each quiet move, by colour and cell indices, caused a cutoff, weighted by depth.
+    history: Vec<i32>,
+    nodes: u64,
+    deadline: Option<f64>,
+    stopped: bool
+}
+
+impl Searcher {
+    fn get_history_index(&self, move_info: &Move) -> usize {
+        let color = if move_info.piece.color == PieceColor::White { 0 } else { 1 };
+        let from = bitboard::get_cell_index(&move_info.from).unwrap_or(0);
+        let to = bitboard::get_cell_index(&move_info.to).unwrap_or(0);
+        (color * bitboard::CELL_COUNT + from) * bitboard::CELL_COUNT + to
+    }
+
+    fn get_captured_value(&self, move_info: &Move) -> Option<i32> {
+        match self.board.0[move_info.to.0][move_info.to.1] {
+            Some(piece) if piece.color != PieceColor::None && piece.color != move_info.piece.color => {
+                Some(eval::get_piece_value(piece.piece_type))
+            },
+            _ if moves::is_en_passant_capture(&self.board, &move_info.from, &move_info.to) => Some(eval::get_piece_value(PieceType::Pawn)),
+            _ => None
+        }
+    }
+
+    /// Sorts the moves best first: the expected best move, captures by most valuable victim and least valuable
+    /// attacker, promotions, killer moves and then quiet moves by their history score.
+    fn order_moves(&self, position_moves: &mut [Move], ply: usize, best_move: Option<Move>) {
+        let killers = self.killers.get(ply).copied().unwrap_or([None; 2]);
+        position_moves.sort_by_cached_key(|move_info| {
+            let score = if Some(*move_info) == best_move {
+                4_000_000
+            } else if let Some(victim) = self.get_captured_value(move_info) {
+                3_000_000 + victim * 16 - eval::get_piece_value(move_info.piece.piece_type) / 100
+            } else if move_info.promotion.is_some() {
+                2_000_000 + move_info.promotion.map_or(0, eval::get_piece_value)
+            } else if killers[0] == Some(*move_info) {
+                1_500_000
+            } else if killers[1] == Some(*move_info) {
+                1_400_000
+            } else {
+                self.history[self.get_history_index(move_info)].min(1_000_000)
+            };
+            -score
+        });
+    }
+
+    fn make(&mut self, move_info: &Move) -> SearchUndo {
+        let hash = self.hashes.last().copied().unwrap_or_default();
+        let is_capture = self.get_captured_value(move_info).is_some();
+        let undo = moves::make_move(&mut self.board, move_info);
+        let search_undo = SearchUndo { undo, en_passant: self.en_passant, halfmove_clock: self.halfmove_clock };
+
+        self.en_passant = moves::get_en_passant_target(move_info);
+        self.hashes.push(zobrist::update_hash(hash, &undo, search_undo.en_passant, self.en_passant));
+        if is_capture || move_info.piece.piece_type == PieceType::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.next_turn = pieces::get_opponent_color(self.next_turn);
+        search_undo
+    }
+
+    fn unmake(&mut self, search_undo: &SearchUndo) {
+        moves::unmake_move(&mut self.board, &search_undo.undo);
+        self.hashes.pop();
+        self.en_passant = search_undo.en_passant;
+        self.halfmove_clock = search_undo.halfmove_clock;
+        self.next_turn = pieces::get_opponent_color(self.next_turn);
+    }
+
+    /// Counts a node and stops the search once the time is up.
+    fn visit(&mut self) {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) && self.deadline.is_some_and(|deadline| get_time_ms() >= deadline) {
+            self.stopped = true;
+        }
+    }
+
+    /// Checks if the position on the search path is drawn by repetition or the fifty-move rule.
+    ///
+    /// A single repetition is enough, as whatever was best the first time can be repeated again.
+    fn is_draw(&self) -> bool {
+        if self.halfmove_clock >= crate::game::FIFTY_MOVE_RULE_HALFMOVES {
+            return true;
+        }
+        match self.hashes.split_last() {
+            Some((current, earlier)) => earlier.iter().rev().take(self.halfmove_clock as usize).any(|hash| hash == current),
+            None => false
+        }
+    }
+
+    /// Scores a position without legal moves for the side to move.
+    fn get_terminal_score(&self, ply: usize) -> i32 {
+        if validation::is_check(&self.board, self.next_turn) {
+            -(MATE_SCORE - ply as i32)
+        } else {
+            self.stalemate_score
+        }
+    }
+
+    /// Searches only captures until the position is quiet, so the evaluation isn't taken in the middle of an exchange.
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.visit();
+        let mut position_moves = encoding::get_sorted_legal_moves(&self.board, self.next_turn, self.en_passant);
+        if position_moves.is_empty() {
+            return self.get_terminal_score(ply);
+        }
+        let stand_pat = eval::evaluate(&self.board, self.next_turn);
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        position_moves.retain(|move_info| self.get_captured_value(move_info).is_some());
+        self.order_moves(&mut position_moves, ply, None);
+        for move_info in position_moves {
+            let search_undo = self.make(&move_info);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.unmake(&search_undo);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// Negamax with alpha-beta pruning. Returns the score of the side to move and the best move found, if any.
+    fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32, best_move: Option<Move>) -> (i32, Option<Move>) {
+        if ply > 0 && self.is_draw() {
+            return (0, None);
+        }
+        if depth == 0 {
+            return (self.quiescence(ply, alpha, beta), None);
+        }
+        self.visit();
+
+        let mut position_moves = encoding::get_sorted_legal_moves(&self.board, self.next_turn, self.en_passant);
+        if position_moves.is_empty() {
+            return (self.get_terminal_score(ply), None);
+        }
+        self.order_moves(&mut position_moves, ply, best_move);
+
+        let mut best = (-INFINITY, None);
+        for move_info in position_moves {
+            let is_quiet = self.get_captured_value(&move_info).is_none();
+            let search_undo = self.make(&move_info);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha, None).0;
+            self.unmake(&search_undo);
+            if self.stopped {
+                return best;
+            }
+
+            if score > best.0 {
+                best = (score, Some(move_info));
+            }
+            if score >= beta {
+                if is_quiet {
+                    if self.killers.len() <= ply {
+                        self.killers.resize(ply + 1, [None; 2]);
+                    }
+                    if self.killers[ply][0] != Some(move_info) {
+                        self.killers[ply] = [Some(move_info), self.killers[ply][0]];
+                    }
+                    let index = self.get_history_index(&move_info);
+                    self.history[index] += (depth * depth) as i32;
+                }
+                return best;
+            }
+            alpha = alpha.max(score);
+        }
+        best
+    }
+}
+
+/// Finds the best move of the side to move by iterative deepening.
+///
+/// `history` holds the hashes of the positions before this one, for repetition draws. `rule` decides how Stalemate
+/// is scored.
+pub fn search(position: &Position, history: &[u64], limit: SearchLimit, rule: ScoringRule) -> SearchResult {
+    let mut hashes = history.to_vec();
+    hashes.push(zobrist::get_hash(&position.board, position.next_turn, position.en_passant));
+    let (max_depth, deadline) = match limit {
+        SearchLimit::Depth(depth) => (depth.clamp(1, MAX_DEPTH), None),
+        SearchLimit::Time(time_ms) => (MAX_DEPTH, Some(get_time_ms() + time_ms as f64))
+    };
+    let mut searcher = Searcher {
+        board: position.board,
+        next_turn: position.next_turn,
+        en_passant: position.en_passant,
+        halfmove_clock: position.halfmove_clock,
+        hashes,
+        stalemate_score: get_stalemate_score(rule),
+        killers: Vec::new(),
+        history: vec![0; 2 * bitboard::CELL_COUNT * bitboard::CELL_COUNT],
+        nodes: 0,
+        deadline: None,
+        stopped: false
+    };
+
+    let mut result = SearchResult { best_move: None, score: 0, depth: 0, nodes: 0 };
+    for depth in 1..=max_depth {
+        let (score, best_move) = searcher.negamax(depth, 0, -INFINITY, INFINITY, result.best_move);
+        if searcher.stopped {
+            break;
+        }
+        result = SearchResult { best_move, score, depth, nodes: searcher.nodes };
+        if best_move.is_none() || score.abs() >= MATE_SCORE - MAX_PLY as i32 {
+            break;
+        }
+        // The first depth runs without the deadline, so there is a move to play however short the time.
+        searcher.deadline = deadline;
+    }
+    result.nodes = searcher.nodes;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::{self, read_position};
+
+    #[test]
+    fn finds_mate_in_one() {
+        // The Rook on e9 mates the Black King on f11 from f10.
+        let position = read_position("k/3/1R3/2PP3/1n3b3/4p2B1n1/4P3b2/P7P2/9P1/11/10K w - 0 1").unwrap();
+        let result = search(&position, &[], SearchLimit::Depth(3), ScoringRule::Glinski);
+        assert_eq!(result.score, MATE_SCORE - 1);
+        assert_eq!(result.depth, 1);
+
+        let mut board = position.board;
+        moves::make_move(&mut board, &result.best_move.unwrap());
+        assert!(validation::is_check(&board, PieceColor::Black));
+        assert!(encoding::get_sorted_legal_moves(&board, PieceColor::Black, None).is_empty());
+    }
+
+    #[test]
+    fn takes_a_hanging_queen() {
+        // The White Rook on f4 can take the undefended Black Queen on f8.
+        let position = read_position("1/3/5/k2q3/9/11/11/5R5/11/11/6K4 w - 0 1").unwrap();
+        let result = search(&position, &[], SearchLimit::Depth(2), ScoringRule::Glinski);
+        let best_move = result.best_move.unwrap();
+        assert_eq!(best_move.piece.piece_type, PieceType::Rook);
+        assert_eq!(position.board.0[best_move.to.0][best_move.to.1].unwrap().piece_type, PieceType::Queen);
+        assert!(result.score > eval::get_piece_value(PieceType::Rook));
+    }
+
+    #[test]
+    fn stops_in_time() {
+        let start = get_time_ms();
+        let result = search(&position::get_default_position(), &[], SearchLimit::Time(200), ScoringRule::Glinski);
+        assert!(result.best_move.is_some());
+        assert!(result.depth >= 1);
+        assert!(get_time_ms() - start < 2000.0);
+
+        // Even with no time at all the first depth finishes.
+        let result = search(&position::get_default_position(), &[], SearchLimit::Time(0), ScoringRule::Glinski);
+        assert!(result.best_move.is_some());
+        assert!(result.depth >= 1);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::board::{self, Board};
+use crate::cell::Cell;
+use crate::encoding;
+use crate::moves::Move;
+use crate::pieces::{ChessPiece, PieceColor, PieceType};
+use crate::position::{self, Position};
+
+/// Positions reached by random play from the start: an en passant capture for Black, two White Pawns that can
+/// promote, White in Check with 5 replies and a sparse middlegame with long slider moves.
+pub const EN_PASSANT: &str = "1/2k/1b3/3n2r/3pp1p2/1pr1PpP1pp1/2pn7/1B1P7/3Q4PP1/2PRBBb2q1/1PR1K6 b g5 0 1";
+pub const PROMOTION: &str = "1/3/3k1/7/4R4/1P2PP3P1/p1pb7/2P3P1K2/8Pb1/n10/6N4 w - 0 1";
+pub const CHECK: &str = "1/q1k/1b2n/r5r/p2pp1pp1/3p1Pp2p1/2p8/3PPRP4/n1N2Q1PbP1/1bP2B2P2/1PR1BBK4 w - 0 1";
+pub const OPEN: &str = "1/2b/1qk2/7/3pB4/3P1Q5/11/3b4K2/8P2/1P3N5/11 b - 0 1";
+
+pub const POSITIONS: [&str; 4] = [EN_PASSANT, PROMOTION, CHECK, OPEN];
+
+/// Sets up a board with the White King on a1, the Black King on l1 and the given pieces, e.g. ("c3", White, Bishop).
+pub fn get_board(pieces: &[(&str, PieceColor, PieceType)]) -> Board {
+    let mut board = board::new();
+    let kings = [("a1", PieceColor::White, PieceType::King), ("l1", PieceColor::Black, PieceType::King)];
+    for (notation, color, piece_type) in kings.iter().chain(pieces) {
+        let (x, y) = Cell::from_notation(notation).unwrap().to_coordinates();
+        board.0[x][y] = Some(ChessPiece { piece_type: *piece_type, color: *color });
+    }
+    board
+}
+
+/// A game of pseudo-random legal moves, yielding each position together with the move played from it.
+/// The same seed always plays the same game, and the game ends when the side to move has no legal moves.
+pub struct RandomGame {
+    position: Position,
+    seed: u64
+}
+
+impl RandomGame {
+    pub fn new(position: Position, seed: u64) -> RandomGame {
+        RandomGame { position, seed }
+    }
+}
+
+impl Iterator for RandomGame {
+    type Item = (Position, Move);
+
+    fn next(&mut self) -> Option<(Position, Move)> {
+        let legal_moves = encoding::get_sorted_legal_moves(&self.position.board, self.position.next_turn, self.position.en_passant);
+        if legal_moves.is_empty() {
+            return None;
+        }
+        self.seed = self.seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let move_info = legal_moves[(self.seed >> 33) as usize % legal_moves.len()];
+
+        let before = self.position;
+        position::play_move(&mut self.position, &move_info);
+        Some((before, move_info))
+    }
+}
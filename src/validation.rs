@@ -1,41 +1,70 @@
-use crate::pieces::{PieceColor, PieceType, Piece, get_all_pieces};
-use crate::moves::{get_legal_moves, MoveList};
+use crate::pieces::{PieceColor, PieceType, get_all_pieces, get_opponent_color};
+use crate::moves::{is_coordinate_in_bounds, is_square_attacked, MoveList};
 use crate::board::Board;
+use crate::cell::Cell;
+use std::fmt;
 
-pub fn is_check(board: &Board, color_to_check: PieceColor) -> bool {
-    // find color_to_check's King's coordinates on the board
-    let king_position = {
-        let mut king_coordinates: Vec<(usize, usize)> = Vec::new();
-        let all_pieces = get_all_pieces(board, color_to_check);
-        for piece in all_pieces {
-            if piece.piece.piece_type == PieceType::King {
-                king_coordinates.push((piece.x, piece.y));
-            }
-        }
-        king_coordinates
-    };
-
-    let mut check = false;
-    let opponent: PieceColor = match color_to_check {
-        PieceColor::Black => PieceColor::White,
-        PieceColor::White => PieceColor::Black,
-        PieceColor::None => PieceColor::None
-    };
-    let mut opponent_moves: Vec<(usize, usize)> = Vec::new();
-    
-    let opponent_pieces: Vec<Piece> = get_all_pieces(board, opponent);
-
-    for piece in opponent_pieces {
-        let mut current_piece_moves = get_legal_moves(board, &(piece.x, piece.y));
-        opponent_moves.extend(current_piece_moves.drain(..));
-    }
-
-    if king_position.len() > 0 {
-        if opponent_moves.contains(&king_position[0]) {
-            check = true;
+/// Reasons a requested move can be refused.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MoveError {
+    /// The coordinate string is neither of the form "x,y" nor a cell in Glinski notation.
+    BadCoordinate(String),
+    /// The coordinate doesn't lie on the hexagonal board.
+    OffBoard((usize, usize)),
+    /// There is no piece on the cell to move from.
+    NoPiece((usize, usize)),
+    /// The piece on the cell belongs to the side that isn't on turn.
+    WrongSide((usize, usize)),
+    /// The piece can't legally move to the target cell.
+    IllegalMove((usize, usize), (usize, usize)),
+    /// The promotion piece isn't one of "Q", "R", "B", "N", or the move doesn't promote a Pawn.
+    InvalidPromotion(String),
+    /// The Game has already ended.
+    GameOver
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::BadCoordinate(value) => write!(f, "Bad coordinate \"{}\", expected \"x,y\" or a cell like \"f5\"", value),
+            MoveError::OffBoard((x, y)) => write!(f, "Coordinate {},{} is off the board", x, y),
+            MoveError::NoPiece((x, y)) => write!(f, "There is no piece on {},{}", x, y),
+            MoveError::WrongSide((x, y)) => write!(f, "The piece on {},{} belongs to the other side", x, y),
+            MoveError::IllegalMove(from, to) => write!(f, "Illegal move from {},{} to {},{}", from.0, from.1, to.0, to.1),
+            MoveError::InvalidPromotion(piece) => write!(f, "Invalid promotion piece \"{}\"", piece),
+            MoveError::GameOver => write!(f, "The game is over")
         }
     }
-    check
+}
+
+impl std::error::Error for MoveError {}
+
+/// Parses an "x,y" coordinate string, or a cell in Glinski notation like "f5", and checks that it lies on the board.
+pub fn parse_coordinate(coordinate: &str) -> Result<(usize, usize), MoveError> {
+    let parts: Vec<&str> = coordinate.split(',').collect();
+    if parts.len() != 2 {
+        return match Cell::from_notation(coordinate) {
+            Some(cell) => Ok(cell.to_coordinates()),
+            None => Err(MoveError::BadCoordinate(coordinate.to_string()))
+        };
+    }
+    let x: usize = parts[0].trim().parse().map_err(|_| MoveError::BadCoordinate(coordinate.to_string()))?;
+    let y: usize = parts[1].trim().parse().map_err(|_| MoveError::BadCoordinate(coordinate.to_string()))?;
+    if !is_coordinate_in_bounds(&(x, y)) {
+        return Err(MoveError::OffBoard((x, y)));
+    }
+    Ok((x, y))
+}
+
+/// Checks if the King of `color_to_check` is attacked. A side without a King is never in Check.
+pub fn is_check(board: &Board, color_to_check: PieceColor) -> bool {
+    let king = get_all_pieces(board, color_to_check)
+        .into_iter()
+        .find(|piece| piece.piece.piece_type == PieceType::King);
+    match king {
+        Some(king) => is_square_attacked(board, &(king.x, king.y), get_opponent_color(color_to_check)),
+        None => false
+    }
 }
 
 /// Function for checking if the player is in Checkmate or Stalemate
@@ -47,4 +76,52 @@ pub fn has_legal_moves(move_list: &MoveList) -> bool {
         return false;
     }
     true
-}
\ No newline at end of file
+}
+
+/// Checks if neither side can possibly give Checkmate, in which case the Game is a Draw.
+///
+/// That is only certain for a lone King against a lone King, or against a King with a single Knight or Bishop. With
+/// any more material a mate exists, if only with the help of the other side, so the Game goes on.
+pub fn is_insufficient_material(board: &Board) -> bool {
+    let mut minor_pieces = 0;
+    for color in [PieceColor::White, PieceColor::Black] {
+        for piece in get_all_pieces(board, color) {
+            match piece.piece.piece_type {
+                PieceType::King | PieceType::None => {},
+                PieceType::Knight | PieceType::Bishop => minor_pieces += 1,
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen => return false
+            }
+        }
+    }
+    minor_pieces <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::get_board;
+
+    #[test]
+    fn draws_when_mate_is_impossible() {
+        assert!(is_insufficient_material(&get_board(&[])));
+        assert!(is_insufficient_material(&get_board(&[("f6", PieceColor::White, PieceType::Knight)])));
+        assert!(is_insufficient_material(&get_board(&[("f6", PieceColor::Black, PieceType::Bishop)])));
+    }
+
+    #[test]
+    fn plays_on_when_mate_is_possible() {
+        let cases: [&[(&str, PieceColor, PieceType)]; 7] = [
+            &[("f6", PieceColor::White, PieceType::Pawn)],
+            &[("f6", PieceColor::Black, PieceType::Rook)],
+            &[("f6", PieceColor::White, PieceType::Queen)],
+            &[("f6", PieceColor::White, PieceType::Knight), ("c3", PieceColor::Black, PieceType::Knight)],
+            &[("f6", PieceColor::White, PieceType::Knight), ("c3", PieceColor::White, PieceType::Bishop)],
+            // f2 and f3 have different cell colours, f3 and f6 the same one.
+            &[("f2", PieceColor::White, PieceType::Bishop), ("f3", PieceColor::White, PieceType::Bishop)],
+            &[("f3", PieceColor::White, PieceType::Bishop), ("f6", PieceColor::Black, PieceType::Bishop)]
+        ];
+        for pieces in cases {
+            assert!(!is_insufficient_material(&get_board(pieces)), "{:?}", pieces);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::bitboard::{self, CELL_COUNT};
+use crate::board::Board;
+use crate::moves::{self, UndoRecord};
+use crate::pieces::{ChessPiece, PieceColor, PieceType};
+use std::sync::OnceLock;
+
+/// Seed of the key generator. Changing it changes every hash, so stored hashes would no longer match.
+const SEED: u64 = 0x4845_5843_4845_5353;
+
+struct Keys {
+    /// Indexed by cell, colour (White, Black) and piece type (Pawn, Knight, Bishop, Rook, Queen, King).
+    pieces: [[[u64; 6]; 2]; CELL_COUNT],
+    black_to_move: u64,
+    en_passant: [u64; CELL_COUNT]
+}
+
+static KEYS: OnceLock<Keys> = OnceLock::new();
+
+/// Returns the next number of the splitmix64 sequence, which is fast and spreads the bits of a counter well.
+fn get_next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut key = *state;
+    key = (key ^ (key >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    key ^ (key >> 31)
+}
+
+fn get_keys() -> &'static Keys {
+    KEYS.get_or_init(|| {
+        let mut state = SEED;
+        let mut keys = Keys { pieces: [[[0; 6]; 2]; CELL_COUNT], black_to_move: 0, en_passant: [0; CELL_COUNT] };
+        for cell in keys.pieces.iter_mut() {
+            for color in cell.iter_mut() {
+                for key in color.iter_mut() {
+                    *key = get_next_key(&mut state);
+                }
+            }
+        }
+        keys.black_to_move = get_next_key(&mut state);
+        for key in keys.en_passant.iter_mut() {
+            *key = get_next_key(&mut state);
+        }
+        keys
+    })
+}
+
+/// Returns the key of the piece on the cell, or 0 for an empty cell.
+fn get_piece_key(coordinates: &(usize, usize), piece: Option<ChessPiece>) -> u64 {
+    let Some(piece) = piece else { return 0 };
+    let color = match piece.color {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+        PieceColor::None => return 0
+    };
+    let piece_type = match piece.piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+        PieceType::None => return 0
+    };
+    bitboard::get_cell_index(coordinates).map_or(0, |cell| get_keys().pieces[cell][color][piece_type])
+}
+
+/// Returns the key of the en passant cell, or 0 if there is none.
+pub fn get_en_passant_key(en_passant: Option<(usize, usize)>) -> u64 {
+    en_passant.and_then(|cell| bitboard::get_cell_index(&cell)).map_or(0, |cell| get_keys().en_passant[cell])
+}
+
+/// Hashes the position from scratch: the pieces on every cell, the side to move and the en passant cell.
+pub fn get_hash(board: &Board, next_turn: PieceColor, en_passant: Option<(usize, usize)>) -> u64 {
+    let mut hash = get_en_passant_key(en_passant);
+    if next_turn == PieceColor::Black {
+        hash ^= get_keys().black_to_move;
+    }
+    for index in 0..CELL_COUNT {
+        let (x, y) = bitboard::get_cell_coordinates(index);
+        hash ^= get_piece_key(&(x, y), board.0[x][y]);
+    }
+    hash
+}
+
+/// Returns what a move changes in the hash: the pieces it moves and captures and the side to move.
+///
+/// XOR it into the hash, along with the en passant keys before and after, both to apply the move and to revert it.
+pub fn get_move_key(undo: &UndoRecord) -> u64 {
+    let move_info = &undo.move_info;
+    let mut placed = move_info.piece;
+    if let Some(promotion) = moves::get_promotion(move_info) {
+        placed.piece_type = promotion;
+    }
+    let captured = undo.captured.map_or(0, |captured| get_piece_key(&(captured.x, captured.y), Some(captured.piece)));
+    get_piece_key(&move_info.from, Some(move_info.piece))
+        ^ get_piece_key(&move_info.to, Some(placed))
+        ^ captured
+        ^ get_keys().black_to_move
+}
+
+/// Returns the hash after a move made with `moves::make_move`, given the hash before it.
+///
+/// Calling it again with the same arguments on the result gives back the hash from before the move.
+pub fn update_hash(
+    hash: u64,
+    undo: &UndoRecord,
+    en_passant_before: Option<(usize, usize)>,
+    en_passant_after: Option<(usize, usize)>
+) -> u64 {
+    hash ^ get_move_key(undo) ^ get_en_passant_key(en_passant_before) ^ get_en_passant_key(en_passant_after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pieces;
+    use crate::position;
+    use crate::test_support::RandomGame;
+
+    #[test]
+    fn incremental_hash_matches_full_recompute() {
+        for seed in 0..12u64 {
+            let start = position::get_default_position();
+            let (mut board, mut next_turn, mut en_passant) = (start.board, start.next_turn, start.en_passant);
+            let mut hash = get_hash(&board, next_turn, en_passant);
+            let mut history: Vec<(UndoRecord, Option<(usize, usize)>)> = Vec::new();
+
+            for (_, move_info) in RandomGame::new(start, seed).take(120) {
+                let undo = moves::make_move(&mut board, &move_info);
+                let next_en_passant = moves::get_en_passant_target(&move_info);
+                hash = update_hash(hash, &undo, en_passant, next_en_passant);
+                history.push((undo, en_passant));
+                en_passant = next_en_passant;
+                next_turn = pieces::get_opponent_color(next_turn);
+                assert_eq!(hash, get_hash(&board, next_turn, en_passant));
+            }
+
+            while let Some((undo, previous_en_passant)) = history.pop() {
+                hash = update_hash(hash, &undo, previous_en_passant, en_passant);
+                moves::unmake_move(&mut board, &undo);
+                en_passant = previous_en_passant;
+                next_turn = pieces::get_opponent_color(next_turn);
+                assert_eq!(hash, get_hash(&board, next_turn, en_passant));
+            }
+            assert_eq!(board, start.board);
+        }
+    }
+
+    #[test]
+    fn distinguishes_side_to_move_and_en_passant() {
+        let board = position::get_default_position().board;
+        let white = get_hash(&board, PieceColor::White, None);
+        assert_ne!(white, get_hash(&board, PieceColor::Black, None));
+        assert_ne!(white, get_hash(&board, PieceColor::White, Some((5, 5))));
+        assert_eq!(white, get_hash(&board, PieceColor::White, None));
+    }
+}
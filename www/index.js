@@ -3,6 +3,7 @@ import * as wasm from "hexagonal_chess";
 const game = wasm.Game.new();
 let board = JSON.parse(game.get_board());
 let moves = JSON.parse(game.get_current_player_moves());
+let promotionMoves = JSON.parse(game.get_current_player_promotion_moves());
 
 const getBoard = () => {
     const entries = Object.entries(board);
@@ -69,6 +70,7 @@ const refreshBoard = () => {
     resetBoard();
     board = JSON.parse(game.get_board());
     moves = JSON.parse(game.get_current_player_moves());
+    promotionMoves = JSON.parse(game.get_current_player_promotion_moves());
     const entries = Object.entries(board);
     for (const [key, value] of entries) {
         const hexagonElement = document.getElementById(key);
@@ -81,6 +83,40 @@ const refreshBoard = () => {
     }
 }
 
+document.getElementById("undo").addEventListener("click", () => {
+    if (game.undo()) {
+        refreshBoard();
+        resetHexagonColors();
+    }
+});
+
+document.getElementById("redo").addEventListener("click", () => {
+    if (game.redo()) {
+        refreshBoard();
+        resetHexagonColors();
+    }
+});
+
+document.getElementById("computer-move").addEventListener("click", () => {
+    const bestMove = game.best_move_time(1000);
+    if (!bestMove) {
+        return;
+    }
+    const { from, to, promotion } = JSON.parse(bestMove);
+    console.log("Computer moves from " + from + " to " + to);
+    try {
+        game.make_move_with_promotion(from, to, promotion);
+    } catch (error) {
+        console.error(error);
+        return;
+    }
+    refreshBoard();
+    resetHexagonColors();
+    if (game.get_result() !== "Ongoing") {
+        console.log(game.get_result() + "! Winner: " + (game.get_winner() || "none"));
+    }
+});
+
 for (const hexagon of allHexagons) {
     hexagon.addEventListener("click", () => {
         const sessionHexagonId = sessionStorage.getItem("lastSelectedHexagonId");
@@ -88,10 +124,23 @@ for (const hexagon of allHexagons) {
             const allowedMoves = moves[sessionHexagonId];
             if (allowedMoves && allowedMoves.includes(hexagon.id)) {
                 console.log("Move from " + sessionHexagonId + " to " + hexagon.id);
-                game.make_move(sessionHexagonId, hexagon.id);
-                game.switch_player();
+                const allowedPromotions = promotionMoves[sessionHexagonId];
+                try {
+                    if (allowedPromotions && allowedPromotions.includes(hexagon.id)) {
+                        const piece = prompt("Promote to (Q, R, B, N):", "Q") || "Q";
+                        game.make_move_with_promotion(sessionHexagonId, hexagon.id, piece.toUpperCase());
+                    } else {
+                        game.make_move(sessionHexagonId, hexagon.id);
+                    }
+                } catch (error) {
+                    console.error(error);
+                    return;
+                }
                 refreshBoard();
                 resetHexagonColors();
+                if (game.get_result() !== "Ongoing") {
+                    console.log(game.get_result() + "! Winner: " + (game.get_winner() || "none"));
+                }
                 if (game.is_in_check()) {
                     console.log("Check!");
                     const entries = Object.entries(board);